@@ -0,0 +1,235 @@
+// Compares the grid's compact cells against the old layout, where every cell held a full copy of its particle's data
+// Run with `cargo bench --bench cell_storage`
+use bog_engine::core::engine::Engine;
+use bog_engine::io::null_renderer::NullRenderer;
+use bog_engine::settings::Settings;
+use bog_engine::structs::color::Color;
+use bog_engine::structs::grid::Grid;
+use bog_engine::structs::particle::Particle;
+use bog_engine::structs::particle_data::ParticleData;
+use bog_engine::structs::utils::{Offset2, Vector2};
+
+use std::mem::size_of;
+use std::time::{Duration, Instant};
+
+const GRID_WIDTH: usize = 1024;
+const GRID_HEIGHT: usize = 768;
+const MOVE_PASSES: usize = 40;
+const RUN_COUNT: usize = 5;
+
+const WATER_PARTICLE_ID: u16 = 100;
+const SAND_PARTICLE_ID: u16 = 300;
+
+// What a cell used to store before static properties were looked up by ID
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+struct LegacyParticle {
+    handle: u32,
+    id: u16,
+    name: String,
+    category: u16,
+    color: Color,
+    variant: u8,
+    position: Vector2<i32>,
+    index: u32,
+    is_movable: bool,
+    density: f32,
+    age: u32,
+    burn_timer: u32,
+}
+
+impl LegacyParticle {
+    fn new(particle_data: &ParticleData, index: usize) -> LegacyParticle {
+        return LegacyParticle {
+            handle: index as u32,
+            id: particle_data.id,
+            name: particle_data.name.clone(),
+            category: particle_data.category,
+            color: Color::from_hex(&particle_data.base_color),
+            variant: 0,
+            position: Vector2::<i32>::new((index % GRID_WIDTH) as i32, (index / GRID_WIDTH) as i32),
+            index: index as u32,
+            is_movable: particle_data.is_movable,
+            density: particle_data.density,
+            age: 0,
+            burn_timer: 0,
+        };
+    }
+}
+
+// Bands of sand over water, the sand sinks through the water for the whole run
+fn create_grid() -> Grid {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let settings: Settings = Settings {
+        game_width: GRID_WIDTH,
+        game_height: GRID_HEIGHT,
+        debug_enabled: false,
+        seed: Some(1),
+        ..Settings::default()
+    };
+    let mut engine: Engine = Engine::new(&settings, &mut renderer).unwrap();
+
+    for y in 0..GRID_HEIGHT as i32 {
+        let particle_id: u16 = if (y / 16) % 2 == 0 { WATER_PARTICLE_ID } else { SAND_PARTICLE_ID };
+        for x in 0..GRID_WIDTH as i32 {
+            engine.current_grid.create_particle_at(x, y, particle_id, false, false);
+        }
+    }
+    return engine.current_grid;
+}
+
+// The old grid, just enough of it to move particles around the way it used to
+struct LegacyGrid {
+    width: i32,
+    height: i32,
+    data: Vec<LegacyParticle>,
+    temperature: Vec<f32>,
+}
+
+impl LegacyGrid {
+    fn is_in_bounds(&self, x: i32, y: i32) -> bool {
+        return x >= 0 && x < self.width && y >= 0 && y < self.height;
+    }
+
+    // The old try_move_particle, without the random bumps and dirty marking neither run uses
+    fn try_move_particle(&mut self, particle_index: usize, direction_groups: &[Vec<Offset2<i32>>]) -> bool {
+        let current_position = self.data[particle_index].position;
+        let current_density = self.data[particle_index].density;
+        let width = self.width;
+
+        for directions in direction_groups {
+            for direction in directions {
+                let tx: i32 = current_position.x + direction.dx;
+                let ty: i32 = current_position.y + direction.dy;
+
+                if !self.is_in_bounds(tx, ty) {
+                    continue;
+                }
+
+                let target_index: usize = (ty * width + tx) as usize;
+
+                if particle_index == target_index {
+                    continue;
+                }
+
+                let moved: bool = {
+                    let (a, b) = if particle_index < target_index {
+                        let (a, b) = self.data.split_at_mut(target_index);
+                        (&mut a[particle_index], &mut b[0])
+                    } else {
+                        let (a, b) = self.data.split_at_mut(particle_index);
+                        (&mut b[0], &mut a[target_index])
+                    };
+
+                    if b.is_movable && current_density > b.density {
+                        std::mem::swap(a, b);
+                        std::mem::swap(&mut a.position, &mut b.position);
+                        std::mem::swap(&mut a.index, &mut b.index);
+
+                        true
+                    } else {
+                        false
+                    }
+                };
+
+                if moved {
+                    self.temperature.swap(particle_index, target_index);
+                    return true;
+                }
+            }
+        }
+
+        return false;
+    }
+}
+
+// Both runs try to move every cell down one row each pass, bottom row first
+fn run_legacy_moves(grid: &mut LegacyGrid) -> usize {
+    let fall_group: Vec<Vec<Offset2<i32>>> = vec![vec![Offset2::<i32> { dx: 0, dy: -1 }]];
+    let mut move_count: usize = 0;
+
+    for _ in 0..MOVE_PASSES {
+        for index in GRID_WIDTH..GRID_WIDTH * GRID_HEIGHT {
+            if grid.try_move_particle(index, &fall_group) {
+                move_count += 1;
+            }
+        }
+    }
+
+    return move_count;
+}
+
+fn run_grid_moves(grid: &mut Grid) -> usize {
+    let fall_group: Vec<Vec<Offset2<i32>>> = vec![vec![Offset2::<i32> { dx: 0, dy: -1 }]];
+    let (mut cells, _) = grid.cells();
+    let mut move_count: usize = 0;
+
+    for _ in 0..MOVE_PASSES {
+        for index in GRID_WIDTH..GRID_WIDTH * GRID_HEIGHT {
+            if cells.try_move_particle(index, &fall_group, false, false) {
+                move_count += 1;
+            }
+        }
+    }
+
+    return move_count;
+}
+
+fn print_result(name: &str, bytes_per_cell: usize, elapsed: Duration) {
+    println!(
+        "{:<8} {:>4} bytes per cell, {:>6.1} MB, {:>7.2} ns per attempted move",
+        name,
+        bytes_per_cell,
+        (bytes_per_cell * GRID_WIDTH * GRID_HEIGHT) as f64 / (1024.0 * 1024.0),
+        elapsed.as_nanos() as f64 / (MOVE_PASSES * (GRID_HEIGHT - 1) * GRID_WIDTH) as f64
+    );
+}
+
+fn main() {
+    let mut grid: Grid = create_grid();
+    let cell_count: usize = grid.data.len();
+    let (start_data, start_temperature) = (grid.data.clone(), grid.temperature.clone());
+
+    // Rebuild the same world in the old layout
+    let legacy_start: Vec<LegacyParticle> = (0..cell_count)
+        .map(|index| LegacyParticle::new(grid.get_particle_data(grid.data[index].id).unwrap(), index))
+        .collect();
+    let mut legacy_grid: LegacyGrid = LegacyGrid {
+        width: grid.width,
+        height: grid.height,
+        data: legacy_start.clone(),
+        temperature: start_temperature.clone(),
+    };
+
+    // The old layout also kept a heap allocated name in every cell
+    let legacy_bytes: usize = size_of::<LegacyParticle>() + size_of::<f32>() + legacy_start[0].name.capacity();
+    let grid_bytes: usize = size_of::<Particle>() + size_of::<f32>();
+
+    println!(
+        "{}x{} sand sinking through water, {} passes, best of {} runs",
+        GRID_WIDTH, GRID_HEIGHT, MOVE_PASSES, RUN_COUNT
+    );
+
+    // Timings on a busy machine are noisy, so each layout starts over from the same world a few times
+    let mut legacy_best: Duration = Duration::MAX;
+    let mut grid_best: Duration = Duration::MAX;
+    for _ in 0..RUN_COUNT {
+        legacy_grid.data.clone_from(&legacy_start);
+        legacy_grid.temperature.clone_from(&start_temperature);
+        let start: Instant = Instant::now();
+        let legacy_moves: usize = run_legacy_moves(&mut legacy_grid);
+        legacy_best = legacy_best.min(start.elapsed());
+
+        grid.data.clone_from(&start_data);
+        grid.temperature.clone_from(&start_temperature);
+        let start: Instant = Instant::now();
+        let grid_moves: usize = run_grid_moves(&mut grid);
+        grid_best = grid_best.min(start.elapsed());
+
+        // Both layouts have to have done the same work for the numbers to mean anything
+        assert_eq!(legacy_moves, grid_moves);
+    }
+
+    print_result("legacy", legacy_bytes, legacy_best);
+    print_result("compact", grid_bytes, grid_best);
+}
@@ -0,0 +1,76 @@
+// Times physics ticks on a fully active sand and water world under thread pools of different sizes
+// Run with `cargo bench --bench physics`
+use bog_engine::core::engine::Engine;
+use bog_engine::io::null_renderer::NullRenderer;
+use bog_engine::settings::Settings;
+use bog_engine::structs::input_state::InputState;
+
+use std::time::{Duration, Instant};
+
+const GRID_WIDTH: usize = 512;
+const GRID_HEIGHT: usize = 384;
+const WARMUP_TICKS: usize = 5;
+const MEASURED_TICKS: usize = 50;
+
+const WATER_PARTICLE_ID: u16 = 100;
+const SAND_PARTICLE_ID: u16 = 300;
+
+// Alternating stripes of sand and water over empty space, everything keeps falling and mixing for the whole run
+fn create_active_world(renderer: &mut NullRenderer) -> Engine {
+    let settings: Settings = Settings {
+        game_width: GRID_WIDTH,
+        game_height: GRID_HEIGHT,
+        debug_enabled: false,
+        seed: Some(1),
+        ..Settings::default()
+    };
+    let mut engine: Engine = Engine::new(&settings, renderer).unwrap();
+
+    for y in GRID_HEIGHT as i32 / 4..GRID_HEIGHT as i32 {
+        for x in 0..GRID_WIDTH as i32 {
+            let particle_id: u16 = if (x / 8 + y / 8) % 2 == 0 { SAND_PARTICLE_ID } else { WATER_PARTICLE_ID };
+            engine.current_grid.create_particle_at(x, y, particle_id, true, true);
+        }
+    }
+    return engine;
+}
+
+fn measure_tick_time(thread_count: usize) -> Duration {
+    let pool: rayon::ThreadPool = rayon::ThreadPoolBuilder::new().num_threads(thread_count).build().unwrap();
+
+    return pool.install(|| {
+        let mut renderer: NullRenderer = NullRenderer::new();
+        let mut engine: Engine = create_active_world(&mut renderer);
+        let mut input_state: InputState = InputState::default();
+
+        for _ in 0..WARMUP_TICKS {
+            engine.update(&mut renderer, &mut input_state);
+        }
+
+        let start: Instant = Instant::now();
+        for _ in 0..MEASURED_TICKS {
+            engine.update(&mut renderer, &mut input_state);
+        }
+        return start.elapsed() / MEASURED_TICKS as u32;
+    });
+}
+
+fn main() {
+    let available_threads: usize = std::thread::available_parallelism().map_or(1, |count| count.get());
+    let mut thread_counts: Vec<usize> = vec![1, 2, 4, available_threads];
+    thread_counts.retain(|&count| count <= available_threads);
+    thread_counts.dedup();
+
+    println!("{}x{} sand and water, {} ticks per run", GRID_WIDTH, GRID_HEIGHT, MEASURED_TICKS);
+
+    let single_thread_time: Duration = measure_tick_time(1);
+    for thread_count in thread_counts {
+        let tick_time: Duration = if thread_count == 1 { single_thread_time } else { measure_tick_time(thread_count) };
+        println!(
+            "{:>3} threads: {:>8.2} ms per tick, {:.2}x",
+            thread_count,
+            tick_time.as_secs_f64() * 1000.0,
+            single_thread_time.as_secs_f64() / tick_time.as_secs_f64()
+        );
+    }
+}
@@ -1,12 +1,36 @@
-use crate::io::native_renderer::NativeRenderer;
+use crate::core::history::History;
+use crate::core::physics::{ChunkUpdater, create_chunk_rng};
+use crate::core::tools::{Tool, ToolPhase, ToolStroke, create_default_tools};
+use crate::io::image_import::{FitMode, ImageImportError, Palette, import_image};
 use crate::io::renderer_interface::RendererInterface;
+use crate::io::world_file::{WorldFileError, load_grid, save_grid};
+use crate::loader::{ConflictReport, LoadError, load_particle_data_sources};
+use crate::settings::Settings;
+use crate::structs::chunk::CellBounds;
 use crate::structs::color::Color;
+use crate::structs::gravity::Gravity;
+use crate::structs::grid::AMBIENT_TEMPERATURE;
+use crate::structs::grid_cells::{GridCells, SharedGridCells};
 use crate::structs::input_state::InputState;
-use crate::structs::utils::{Offset2, Pixel, Vector2};
-use crate::structs::{grid::Grid, particle::Particle};
+use crate::structs::particle_registry::ParticleRegistry;
+use crate::structs::utils::{Pixel, Vector2};
+use crate::structs::grid::Grid;
 
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::Rng;
+use rayon::prelude::*;
+use std::path::Path;
+
+const EMPTY_PARTICLE_ID: u16 = 0;
+
+// Brush sizes reachable through the scroll wheel and brush keys
+const MIN_BRUSH_RADIUS: i32 = 1;
+const MAX_BRUSH_RADIUS: i32 = 64;
+
+// Chunk parities updated by each checkerboard pass, in order
+const CHECKERBOARD_PASSES: [(usize, usize); 4] = [(0, 0), (1, 0), (0, 1), (1, 1)];
+
+// Temperature deviation from ambient at which the heatmap reaches full intensity
+const HEATMAP_RANGE: f32 = 1000.0;
 
 pub struct Engine {
     pub game_width: usize,
@@ -15,91 +39,732 @@ pub struct Engine {
 
     pub brush_radius: i32,
     pub selected_particle_id: u16,
+    pub debug_enabled: bool,
+
+    // Seed the grid's RNG started from, the same seed and input reproduce a run exactly
+    pub seed: u64,
+
+    pub history: History,
+
+    // Painting tools, and the mask limiting what they paint over
+    pub tools: Vec<Box<dyn Tool>>,
+    pub active_tool: usize,
+    pub replace_mask: Option<u16>,
+    tool_stroke: Option<ToolStroke>,
+
+    // While paused the world only advances one requested step at a time
+    pub is_paused: bool,
+    pending_steps: u32,
+
+    // Strength zero gravity is toggled back to
+    gravity_strength: f32,
+
+    // Particles replaced while merging the particle data, kept around for the caller to show
+    pub particle_data_conflicts: ConflictReport,
 }
 
 impl Engine {
-    pub fn new(game_width: usize, game_height: usize, renderer: &mut dyn RendererInterface) -> Engine {
+    pub fn new(settings: &Settings, renderer: &mut dyn RendererInterface) -> Result<Engine, LoadError> {
+        // Load particle data for the grid
+        let (registry, conflicts): (ParticleRegistry, ConflictReport) = load_particle_data_sources(&settings.particle_data_paths)?;
+
+        // Without a fixed seed every run is different, but can still be replayed from the seed it picked
+        let seed: u64 = settings.seed.unwrap_or_else(|| rand::rng().random());
+
         let mut new_engine = Engine {
-            game_width,
-            game_height,
-            current_grid: Grid::new(game_width as u16, game_height as u16),
+            game_width: settings.game_width,
+            game_height: settings.game_height,
+            current_grid: Grid::new(settings.game_width as u16, settings.game_height as u16, registry, seed),
 
             brush_radius: 8,
             selected_particle_id: 300,
+            debug_enabled: settings.debug_enabled,
+
+            seed: seed,
+
+            history: History::new(settings.undo_memory_budget),
+
+            tools: create_default_tools(),
+            active_tool: 0,
+            replace_mask: None,
+            tool_stroke: None,
+
+            is_paused: false,
+            pending_steps: 0,
+
+            gravity_strength: if settings.gravity.is_zero() { 1.0 } else { settings.gravity.strength },
+
+            particle_data_conflicts: conflicts,
         };
 
         // Populate the grid with empty particles
-        const EMPTY_PARTICLE_ID: u16 = 0;
         new_engine.current_grid.populate(EMPTY_PARTICLE_ID);
+        new_engine.current_grid.set_gravity(settings.gravity);
 
         // Queue grid to be rendered
-        renderer.queue_particles(&new_engine.current_grid.data);
+        new_engine.queue_all_cells(renderer);
+
+        return Ok(new_engine);
+    }
+
+    pub fn save_world(&self, path: &Path) -> Result<(), WorldFileError> {
+        return save_grid(&self.current_grid, path);
+    }
+
+    // Replaces the current grid with a saved one, the world has to match the grid's size
+    pub fn load_world(&mut self, path: &Path, renderer: &mut dyn RendererInterface) -> Result<(), WorldFileError> {
+        let mut grid: Grid = load_grid(path, self.current_grid.get_registry())?;
+        if grid.width != self.current_grid.width || grid.height != self.current_grid.height {
+            return Err(WorldFileError::SizeMismatch {
+                expected: (self.current_grid.width, self.current_grid.height),
+                found: (grid.width, grid.height),
+            });
+        }
+
+        // The loaded world carries on with this run's random numbers
+        grid.rng = self.current_grid.rng.clone();
+
+        // Strokes recorded on the old grid mean nothing on the loaded one
+        self.current_grid = grid;
+        self.history.clear();
+
+        // Zero gravity toggles back to the loaded world's strength, not the old one's
+        let gravity: Gravity = self.current_grid.get_gravity();
+        if !gravity.is_zero() {
+            self.gravity_strength = gravity.strength;
+        }
+        self.queue_all_cells(renderer);
+        return Ok(());
+    }
+
+    // Replaces the grid's contents with a level painted as a PNG, colors are matched against the legend or the particles' own colors
+    pub fn import_level(
+        &mut self,
+        path: &Path,
+        legend_path: Option<&Path>,
+        fit_mode: FitMode,
+        renderer: &mut dyn RendererInterface,
+    ) -> Result<(), ImageImportError> {
+        let palette: Palette = match legend_path {
+            Some(legend_path) => Palette::from_legend_file(legend_path, self.current_grid.get_registry())?,
+            None => Palette::from_registry(self.current_grid.get_registry()),
+        };
+        let particle_ids: Vec<u16> = import_image(path, &palette, self.game_width, self.game_height, fit_mode)?;
+
+        // The import is a single undo step
+        self.history.begin_stroke();
+        for (index, &particle_id) in particle_ids.iter().enumerate() {
+            let x: i32 = (index % self.game_width) as i32;
+            let y: i32 = (index / self.game_width) as i32;
+            self.history.record_cell(&self.current_grid, index as u32);
+            self.current_grid.create_particle_at(x, y, particle_id, true, false);
+        }
+        self.history.end_stroke();
+
+        self.queue_all_cells(renderer);
+        return Ok(());
+    }
+
+    // Reverts the last stroke, returns false if there is nothing to undo
+    // Undo and redo wait for the stroke being painted to finish, so it stays a single undo step
+    pub fn undo(&mut self) -> bool {
+        if self.tool_stroke.is_some() {
+            return false;
+        }
+        return self.history.undo(&mut self.current_grid);
+    }
+
+    pub fn redo(&mut self) -> bool {
+        if self.tool_stroke.is_some() {
+            return false;
+        }
+        return self.history.redo(&mut self.current_grid);
+    }
+
+    // Particles the user can paint with, in ID order
+    pub fn get_selectable_particle_ids(&self) -> Vec<u16> {
+        let mut particle_ids: Vec<u16> = self.current_grid.get_registry().particles.keys().copied().filter(|&id| id != EMPTY_PARTICLE_ID).collect();
+        particle_ids.sort();
+        return particle_ids;
+    }
+
+    // Selects the nth selectable particle, returns false if there aren't that many
+    pub fn select_particle_slot(&mut self, slot: usize) -> bool {
+        match self.get_selectable_particle_ids().get(slot) {
+            Some(&particle_id) => {
+                self.selected_particle_id = particle_id;
+                return true;
+            }
+            None => return false,
+        }
+    }
 
-        return new_engine;
+    // Moves the selection forward or backward through the selectable particles, wrapping around at the ends
+    pub fn cycle_selected_particle(&mut self, direction: i32) {
+        let particle_ids: Vec<u16> = self.get_selectable_particle_ids();
+        if particle_ids.is_empty() {
+            return;
+        }
+
+        let current: i32 = particle_ids.iter().position(|&id| id == self.selected_particle_id).unwrap_or(0) as i32;
+        let next: i32 = (current + direction).rem_euclid(particle_ids.len() as i32);
+        self.selected_particle_id = particle_ids[next as usize];
+    }
+
+    pub fn get_active_tool_name(&self) -> &'static str {
+        return self.tools[self.active_tool].get_name();
+    }
+
+    // Moves forward or backward through the tools, wrapping around at the ends
+    pub fn cycle_active_tool(&mut self, direction: i32) {
+        self.active_tool = (self.active_tool as i32 + direction).rem_euclid(self.tools.len() as i32) as usize;
+    }
+
+    // Restricts painting to the material at a cell, or lifts the restriction if there already is one
+    pub fn toggle_replace_mask_at(&mut self, x: i32, y: i32) {
+        if self.replace_mask.is_some() {
+            self.replace_mask = None;
+            return;
+        }
+        self.replace_mask = self.current_grid.get_particle_at(x, y).map(|particle| particle.id);
+    }
+
+    pub fn resize_brush(&mut self, delta: i32) {
+        self.brush_radius = (self.brush_radius + delta).clamp(MIN_BRUSH_RADIUS, MAX_BRUSH_RADIUS);
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.is_paused = !self.is_paused;
+        self.pending_steps = 0;
+    }
+
+    pub fn set_gravity(&mut self, gravity: Gravity) {
+        if !gravity.is_zero() {
+            self.gravity_strength = gravity.strength;
+        }
+        self.current_grid.set_gravity(gravity);
+    }
+
+    // Turns gravity a quarter turn clockwise, keeping its strength
+    pub fn rotate_gravity(&mut self) {
+        let gravity: Gravity = self.current_grid.get_gravity();
+        self.set_gravity(Gravity::new(gravity.direction.rotate_clockwise(), gravity.strength));
     }
 
-    pub fn update(&mut self, renderer: &mut dyn RendererInterface, input_state: &InputState) {
+    // Switches gravity off, or back on at the strength it had before
+    pub fn toggle_zero_gravity(&mut self) {
+        let gravity: Gravity = self.current_grid.get_gravity();
+        let strength: f32 = if gravity.is_zero() { self.gravity_strength } else { 0.0 };
+        self.set_gravity(Gravity::new(gravity.direction, strength));
+    }
+
+    // Advances a paused world by a single update
+    pub fn request_step(&mut self) {
+        if self.is_paused {
+            self.pending_steps += 1;
+        }
+    }
+
+    // Handles input and runs a single physics tick
+    pub fn update(&mut self, renderer: &mut dyn RendererInterface, input_state: &mut InputState) {
+        self.run_frame(renderer, input_state, 1);
+    }
+
+    // Handles input once, then runs the given number of physics ticks, a frame may run none at all in slow motion
+    pub fn run_frame(&mut self, renderer: &mut dyn RendererInterface, input_state: &mut InputState, ticks: u32) {
         // Process input
         self.handle_input(input_state);
 
-        let mut particles_to_render: Vec<Particle> = Vec::with_capacity(self.current_grid.dirty_particles.len());
+        // While paused only requested steps run, one per frame so each can be seen
+        let mut ticks_to_run: u32 = ticks;
+        if self.is_paused {
+            ticks_to_run = self.pending_steps.min(1);
+            self.pending_steps -= ticks_to_run;
+        }
 
-        for &index in &self.current_grid.dirty_particles {
-            if let Some(particle) = self.current_grid.data.get(index as usize) {
-                particles_to_render.push(particle.clone());
-            }
+        // Painted particles are drawn right away but stay dirty so they move once a tick runs
+        let mut heated_chunks: Vec<usize> = Vec::new();
+        if ticks_to_run == 0 {
+            let dirty_indices: Vec<u32> = self.current_grid.get_dirty_indices();
+            self.queue_cells(renderer, &dirty_indices);
+            heated_chunks = self.current_grid.get_awake_chunk_indices();
+        }
+
+        for _ in 0..ticks_to_run {
+            heated_chunks.extend(self.tick(renderer));
+        }
+
+        // Queue the temperature heatmap on the debug overlay, only where temperatures may have changed
+        if self.debug_enabled {
+            heated_chunks.sort_unstable();
+            heated_chunks.dedup();
+            self.queue_heatmap(renderer, &heated_chunks);
         }
+    }
 
-        // Clear dirty particle set for the next frame
-        self.current_grid.dirty_particles.clear();
+    // Advances the simulation by one fixed step, returning the chunks heat was conducted in
+    fn tick(&mut self, renderer: &mut dyn RendererInterface) -> Vec<usize> {
+        // Take this tick's dirty cells, anything that changes from here on is left for the next tick
+        let updated_indices: Vec<u32> = self.current_grid.take_dirty_indices();
 
         // Update particle physics
-        self.step_physics(&mut particles_to_render);
+        self.step_physics(&updated_indices);
+
+        // Conduct heat between neighboring cells, then let materials change state
+        let heated_chunks: Vec<usize> = self.current_grid.diffuse_heat();
+        self.apply_phase_transitions(&heated_chunks);
+
+        // Queue the updated cells, and every cell that changed along the way
+        self.queue_cells(renderer, &updated_indices);
+        let changed_indices: Vec<u32> = self.current_grid.get_dirty_indices();
+        self.queue_cells(renderer, &changed_indices);
+
+        return heated_chunks;
+    }
+
+    fn queue_cells(&self, renderer: &mut dyn RendererInterface, indices: &[u32]) {
+        let particles: Vec<Pixel> = indices.iter().map(|&index| self.current_grid.get_pixel(index as usize)).collect();
+        renderer.queue_particles(&particles);
+    }
+
+    fn queue_all_cells(&self, renderer: &mut dyn RendererInterface) {
+        let particles: Vec<Pixel> = (0..self.current_grid.data.len()).map(|index| self.current_grid.get_pixel(index)).collect();
+        renderer.queue_particles(&particles);
+    }
+
+    fn queue_heatmap(&self, renderer: &mut dyn RendererInterface, chunk_indices: &[usize]) {
+        let mut pixels: Vec<Pixel> = Vec::new();
+
+        for &chunk_index in chunk_indices {
+            let bounds: CellBounds = self.current_grid.get_chunk_bounds(chunk_index);
+            for y in bounds.min_y..=bounds.max_y {
+                for x in bounds.min_x..=bounds.max_x {
+                    let index: usize = (y * self.current_grid.width + x) as usize;
+                    pixels.push(Self::get_heatmap_pixel(self.current_grid.temperature[index], index));
+                }
+            }
+        }
+
+        renderer.queue_debug_overlay_pixels(pixels);
+    }
+
+    fn get_heatmap_pixel(temperature: f32, index: usize) -> Pixel {
+        let deviation: f32 = temperature - AMBIENT_TEMPERATURE;
 
-        // Queue dirty particles
-        renderer.queue_particles(&particles_to_render);
+        // Cells close to ambient temperature stay transparent
+        if deviation.abs() < 1.0 {
+            return Pixel::from_rgba(0, 0, 0, 0, index);
+        }
+
+        // Hot cells glow from red to yellow, cold cells from deep blue to pale cyan
+        let t: f32 = (deviation.abs() / HEATMAP_RANGE).min(1.0);
+        let color: Color = if deviation > 0.0 {
+            Color::lerp_color(&Color::from_rgba(255, 40, 0, 255), &Color::from_rgba(255, 240, 0, 255), t)
+        } else {
+            Color::lerp_color(&Color::from_rgba(40, 90, 255, 255), &Color::from_rgba(180, 240, 255, 255), t)
+        };
+        let alpha: u8 = (64.0 + t * 160.0) as u8;
+
+        return Pixel::from_rgba(color.r, color.g, color.b, alpha, index);
+    }
+
+    fn handle_input(&mut self, input_state: &mut InputState) {
+        let is_painting: bool = input_state.mouse_left_down || input_state.mouse_right_down;
+        let pos: Vector2<f64> = input_state.mouse_position;
+        let cursor: Vector2<i32> = Vector2::<i32>::new(pos.x.floor() as i32, pos.y.floor() as i32);
+
+        // Brushes fill in the path the mouse took since the last update, so fast strokes stay continuous
+        let previous: Vector2<f64> = input_state.previous_mouse_position.unwrap_or(pos);
+        let previous_cursor: Vector2<i32> = Vector2::<i32>::new(previous.x.floor() as i32, previous.y.floor() as i32);
+        input_state.previous_mouse_position = if is_painting { Some(pos) } else { None };
+
+        // A stroke lasts from the mouse press to its release
+        let phase: ToolPhase = match (&mut self.tool_stroke, is_painting) {
+            (None, true) => {
+                self.history.begin_stroke();
+                self.tool_stroke = Some(ToolStroke {
+                    start: cursor,
+                    previous: cursor,
+                    current: cursor,
+                    // Right click erases
+                    particle_id: if input_state.mouse_right_down { EMPTY_PARTICLE_ID } else { self.selected_particle_id },
+                    brush_radius: self.brush_radius,
+                });
+                ToolPhase::Press
+            }
+            (Some(stroke), true) => {
+                stroke.previous = previous_cursor;
+                stroke.current = cursor;
+                stroke.brush_radius = self.brush_radius;
+                ToolPhase::Drag
+            }
+            (Some(_), false) => ToolPhase::Release,
+            (None, false) => return,
+        };
+
+        // Let the active tool pick the cells, then paint them
+        if let Some(stroke) = self.tool_stroke {
+            let indices: Vec<u32> = self.tools[self.active_tool].apply(&mut self.current_grid, &stroke, phase);
+            self.paint_cells(&indices, stroke.particle_id);
+        }
+
+        if phase == ToolPhase::Release {
+            self.tool_stroke = None;
+            self.history.end_stroke();
+        }
+    }
+
+    fn paint_cells(&mut self, indices: &[u32], particle_id: u16) {
+        for &index in indices {
+            // Only paint over the masked material, if there is one
+            if let Some(mask_id) = self.replace_mask {
+                if self.current_grid.data[index as usize].id != mask_id {
+                    continue;
+                }
+            }
+
+            // Remember the cell before painting over it
+            self.history.record_cell(&self.current_grid, index);
+
+            let x: i32 = index as i32 % self.current_grid.width;
+            let y: i32 = index as i32 / self.current_grid.width;
+            self.current_grid.create_particle_at(x, y, particle_id, true, true);
+        }
+    }
+
+    fn step_physics(&mut self, indices: &[u32]) {
+        let chunk_count: usize = (self.current_grid.get_chunk_count_x() * self.current_grid.get_chunk_count_y()) as usize;
+
+        // Group the dirty cells by chunk, remembering what each cell held when the tick started
+        let mut cells_by_chunk: Vec<Vec<(u32, u16)>> = vec![Vec::new(); chunk_count];
+        let width: i32 = self.current_grid.width;
+        for &index in indices {
+            let chunk_index: usize = self.current_grid.get_chunk_index_at(index as i32 % width, index as i32 / width);
+            let category: u16 = self.current_grid.get_properties(self.current_grid.data[index as usize].id).category;
+            cells_by_chunk[chunk_index].push((index, category));
+        }
+
+        let tick_seed: u64 = self.current_grid.rng.random();
+        let gravity: Gravity = self.current_grid.get_gravity();
+        let chunk_count_x: usize = self.current_grid.get_chunk_count_x() as usize;
+        let shared_cells: SharedGridCells = SharedGridCells::new(self.current_grid.cells().0);
+
+        // Four passes in a checkerboard, chunks in the same pass are a whole chunk apart and update in parallel
+        for (pass_x, pass_y) in CHECKERBOARD_PASSES {
+            cells_by_chunk
+                .par_iter_mut()
+                .enumerate()
+                .filter(|(chunk_index, cells)| {
+                    !cells.is_empty() && chunk_index % chunk_count_x % 2 == pass_x && chunk_index / chunk_count_x % 2 == pass_y
+                })
+                .for_each(|(chunk_index, cells)| {
+                    // Safety: updates stay within MAX_PARTICLE_REACH of their chunk, so no two handles in a pass overlap
+                    // Every move GridCells makes is debug asserted to stay within that reach, velocities are clamped below it
+                    let handle: GridCells = unsafe { shared_cells.handle() };
+                    ChunkUpdater::new(handle, create_chunk_rng(tick_seed, chunk_index), gravity).update(cells);
+                });
+        }
+    }
+
+    fn apply_phase_transitions(&mut self, chunk_indices: &[usize]) {
+        for &chunk_index in chunk_indices {
+            let bounds: CellBounds = self.current_grid.get_chunk_bounds(chunk_index);
+            for y in bounds.min_y..=bounds.max_y {
+                for x in bounds.min_x..=bounds.max_x {
+                    self.apply_phase_transition((y * self.current_grid.width + x) as usize);
+                }
+            }
+        }
+    }
+
+    fn apply_phase_transition(&mut self, index: usize) {
+        let temperature: f32 = self.current_grid.temperature[index];
+        let (x, y) = (index as i32 % self.current_grid.width, index as i32 / self.current_grid.width);
+
+        let particle_data = match self.current_grid.get_particle_data(self.current_grid.data[index].id) {
+            Some(val) => val,
+            None => return,
+        };
+
+        // Boiling takes priority over melting
+        let mut transition_into: Option<u16> = None;
+        if let (Some(melt_point), Some(melt_into)) = (particle_data.melt_point, particle_data.melt_into) {
+            if temperature >= melt_point {
+                transition_into = Some(melt_into);
+            }
+        }
+        if let (Some(boil_point), Some(boil_into)) = (particle_data.boil_point, particle_data.boil_into) {
+            if temperature >= boil_point {
+                transition_into = Some(boil_into);
+            }
+        }
+
+        // Swap the particle in place, keeping the cell's current temperature
+        if let Some(new_particle_id) = transition_into {
+            if self.current_grid.create_particle_at(x, y, new_particle_id, true, true) {
+                self.current_grid.temperature[index] = temperature;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::null_renderer::NullRenderer;
+
+    const STONE_PARTICLE_ID: u16 = 10;
+    const WALL_PARTICLE_ID: u16 = 11;
+    const WOOD_PARTICLE_ID: u16 = 12;
+    const ICE_PARTICLE_ID: u16 = 14;
+    const WATER_PARTICLE_ID: u16 = 100;
+    const OIL_PARTICLE_ID: u16 = 101;
+    const LAVA_PARTICLE_ID: u16 = 102;
+    const SMOKE_PARTICLE_ID: u16 = 200;
+    const STEAM_PARTICLE_ID: u16 = 201;
+    const SAND_PARTICLE_ID: u16 = 300;
+    const VOLCANIC_ASH_PARTICLE_ID: u16 = 304;
+
+    fn create_engine(game_width: usize, game_height: usize, renderer: &mut NullRenderer) -> Engine {
+        let settings: Settings = Settings {
+            game_width: game_width,
+            game_height: game_height,
+            debug_enabled: false,
+            ..Settings::default()
+        };
+        return Engine::new(&settings, renderer).unwrap();
+    }
+
+    fn run_ticks(engine: &mut Engine, renderer: &mut NullRenderer, ticks: usize) {
+        let mut input_state: InputState = InputState {
+            mouse_position: Vector2::<f64>::new(-1.0, -1.0),
+            mouse_left_down: false,
+            mouse_right_down: false,
+            previous_mouse_position: None,
+        };
+        for _ in 0..ticks {
+            engine.update(renderer, &mut input_state);
+        }
+    }
+
+    fn count_in_row(engine: &Engine, y: i32, particle_id: u16) -> usize {
+        let grid: &Grid = &engine.current_grid;
+        return (0..grid.width).filter(|&x| grid.get_particle_at(x, y).unwrap().id == particle_id).count();
+    }
+
+    #[test]
+    fn liquids_spread_flat_and_layer_by_density() {
+        let mut renderer: NullRenderer = NullRenderer::new();
+        let mut engine: Engine = create_engine(40, 30, &mut renderer);
+
+        // Oil poured in first, then water on top of it, into a container five cells wide
+        for y in 0..30 {
+            engine.current_grid.create_particle_at(17, y, STONE_PARTICLE_ID, true, true);
+            engine.current_grid.create_particle_at(23, y, STONE_PARTICLE_ID, true, true);
+        }
+        for y in 0..10 {
+            engine.current_grid.create_particle_at(20, y, OIL_PARTICLE_ID, true, true);
+            engine.current_grid.create_particle_at(20, y + 10, WATER_PARTICLE_ID, true, true);
+        }
+        run_ticks(&mut engine, &mut renderer, 2000);
+
+        // The liquids level out, and the heavier water sinks underneath the oil
+        for y in 0..2 {
+            assert_eq!(count_in_row(&engine, y, WATER_PARTICLE_ID), 5, "row {}", y);
+        }
+        for y in 2..4 {
+            assert_eq!(count_in_row(&engine, y, OIL_PARTICLE_ID), 5, "row {}", y);
+        }
+        assert_eq!(count_in_row(&engine, 4, WATER_PARTICLE_ID) + count_in_row(&engine, 4, OIL_PARTICLE_ID), 0);
     }
 
-    fn handle_input(&mut self, input_state: &InputState) {
-        // Paint or erase particles
-        if input_state.mouse_left_down || input_state.mouse_right_down {
-            let pos: &Vector2<f64> = &input_state.mouse_position;
+    #[test]
+    fn gases_rise_and_dissipate_after_their_lifetime() {
+        let mut renderer: NullRenderer = NullRenderer::new();
+        let mut engine: Engine = create_engine(40, 30, &mut renderer);
+        for x in 10..30 {
+            engine.current_grid.create_particle_at(x, 0, SMOKE_PARTICLE_ID, true, true);
+        }
+
+        // Well within its lifetime the smoke has all risen into the top half
+        run_ticks(&mut engine, &mut renderer, 100);
+        let width: i32 = engine.current_grid.width;
+        let smoke_rows: Vec<i32> = (0..engine.current_grid.data.len() as i32)
+            .filter(|&index| engine.current_grid.data[index as usize].id == SMOKE_PARTICLE_ID)
+            .map(|index| index / width)
+            .collect();
+        assert_eq!(smoke_rows.len(), 20);
+        assert!(smoke_rows.iter().all(|&y| y >= 15), "{:?}", smoke_rows);
+
+        // Smoke lives for 240 ticks, after that it's gone
+        run_ticks(&mut engine, &mut renderer, 200);
+        assert!(engine.current_grid.data.iter().all(|particle| particle.id != SMOKE_PARTICLE_ID));
+    }
 
-            let mut particle_id: u16 = self.selected_particle_id;
-            if input_state.mouse_right_down {
-                particle_id = 0; // We are erasing
+    // Puts a hot cell in the middle of a grid filled with one material and returns how much it has cooled
+    fn measure_heat_loss(particle_id: u16, ticks: usize) -> f32 {
+        let mut renderer: NullRenderer = NullRenderer::new();
+        let mut engine: Engine = create_engine(21, 21, &mut renderer);
+        for y in 0..21 {
+            for x in 0..21 {
+                engine.current_grid.create_particle_at(x, y, particle_id, false, false);
             }
+        }
+        let center_index: usize = 10 * 21 + 10;
+        let neighbor_index: usize = 10 * 21 + 11;
+        engine.current_grid.temperature[center_index] = 500.0;
+        // Heat is only conducted in awake chunks, so wake the hot cell's one
+        engine.current_grid.mark_particle_dirty(10, 10, false);
+        run_ticks(&mut engine, &mut renderer, ticks);
+
+        // Heat flows out of the hot cell into its colder neighbors
+        let temperature: &Vec<f32> = &engine.current_grid.temperature;
+        assert!(temperature[neighbor_index] > AMBIENT_TEMPERATURE);
+        assert!(temperature[center_index] > temperature[neighbor_index]);
+        return 500.0 - temperature[center_index];
+    }
 
-            if pos.x >= 0.0 && pos.x < self.game_width as f64 && pos.y >= 0.0 && pos.y < self.game_height as f64 {
-                self.current_grid
-                    .fill_circle_at(pos.x as i32, pos.y as i32, self.brush_radius, particle_id);
+    #[test]
+    fn heat_flows_from_hot_to_cold_at_a_rate_set_by_conductivity() {
+        // Stone and wood share a heat capacity, but stone conducts three times as well
+        let stone_heat_loss: f32 = measure_heat_loss(STONE_PARTICLE_ID, 5);
+        let wood_heat_loss: f32 = measure_heat_loss(WOOD_PARTICLE_ID, 5);
+        assert!(wood_heat_loss > 0.0);
+        assert!(stone_heat_loss > wood_heat_loss * 2.0, "stone lost {stone_heat_loss}, wood lost {wood_heat_loss}");
+    }
+
+    #[test]
+    fn heatmap_is_clear_at_ambient_and_tints_hot_and_cold_cells() {
+        let ambient_pixel: Pixel = Engine::get_heatmap_pixel(AMBIENT_TEMPERATURE, 0);
+        let hot_pixel: Pixel = Engine::get_heatmap_pixel(AMBIENT_TEMPERATURE + 500.0, 1);
+        let cold_pixel: Pixel = Engine::get_heatmap_pixel(AMBIENT_TEMPERATURE - 30.0, 2);
+
+        assert_eq!(ambient_pixel.a, 0);
+        assert!(hot_pixel.a > 0 && hot_pixel.r > hot_pixel.b);
+        assert!(cold_pixel.a > 0 && cold_pixel.b > cold_pixel.r);
+        assert_eq!((hot_pixel.index, cold_pixel.index), (1, 2));
+    }
+
+    #[test]
+    fn heated_ice_melts_into_water_and_keeps_its_temperature() {
+        let mut renderer: NullRenderer = NullRenderer::new();
+        let mut engine: Engine = create_engine(10, 10, &mut renderer);
+        engine.current_grid.create_particle_at(2, 0, ICE_PARTICLE_ID, true, true);
+        engine.current_grid.create_particle_at(6, 0, ICE_PARTICLE_ID, true, true);
+
+        // Only the ice heated past its melt point turns into water
+        let heated_index: usize = 2;
+        let frozen_index: usize = 6;
+        engine.current_grid.temperature[heated_index] = 45.0;
+        engine.current_grid.temperature[frozen_index] = -5.0;
+        engine.apply_phase_transitions(&[0]);
+
+        assert_eq!(engine.current_grid.data[heated_index].id, WATER_PARTICLE_ID);
+        assert_eq!(engine.current_grid.temperature[heated_index], 45.0);
+        assert_eq!(engine.current_grid.data[frozen_index].id, ICE_PARTICLE_ID);
+
+        // Heated further, the water boils into steam
+        engine.current_grid.temperature[heated_index] = 120.0;
+        engine.apply_phase_transitions(&[0]);
+        assert_eq!(engine.current_grid.data[heated_index].id, STEAM_PARTICLE_ID);
+        assert_eq!(engine.current_grid.temperature[heated_index], 120.0);
+    }
+
+    // Walls every cell in, so nothing placed afterwards can move
+    fn create_walled_engine(game_width: usize, game_height: usize, renderer: &mut NullRenderer) -> Engine {
+        let mut engine: Engine = create_engine(game_width, game_height, renderer);
+        for y in 0..game_height as i32 {
+            for x in 0..game_width as i32 {
+                engine.current_grid.create_particle_at(x, y, WALL_PARTICLE_ID, false, false);
             }
         }
+        return engine;
     }
 
-    fn step_physics(&mut self, particles_to_update: &mut Vec<Particle>) {
-        // Shuffle to randomize horizontal order
-        let mut rng = thread_rng();
-        particles_to_update.shuffle(&mut rng);
+    #[test]
+    fn reacting_pairs_turn_into_their_products_at_the_declared_rate() {
+        let mut renderer: NullRenderer = NullRenderer::new();
+        let mut engine: Engine = create_walled_engine(60, 90, &mut renderer);
 
-        // Sort from bottom to top (increasing y)
-        particles_to_update.sort_by(|a: &Particle, b: &Particle| a.position.y.cmp(&b.position.y));
+        // Water resting on lava, every pair kept apart from the others by walls
+        let mut pair_positions: Vec<(i32, i32)> = Vec::new();
+        for y in (0..90).step_by(3) {
+            for x in (0..60).step_by(2) {
+                engine.current_grid.create_particle_at(x, y, LAVA_PARTICLE_ID, true, false);
+                engine.current_grid.create_particle_at(x, y + 1, WATER_PARTICLE_ID, true, false);
+                pair_positions.push((x, y));
+            }
+        }
+        run_ticks(&mut engine, &mut renderer, 1);
 
-        for particle in particles_to_update {
-            match particle.category {
-                4 => self.handle_sands(particle.index as usize),
-                _ => {}
+        // Reacted pairs became Stone + Steam, the rest are left untouched
+        let mut reacted_pairs: usize = 0;
+        for &(x, y) in &pair_positions {
+            let products: (u16, u16) = (
+                engine.current_grid.get_particle_at(x, y).unwrap().id,
+                engine.current_grid.get_particle_at(x, y + 1).unwrap().id,
+            );
+            if products == (STONE_PARTICLE_ID, STEAM_PARTICLE_ID) {
+                reacted_pairs += 1;
+            } else {
+                assert_eq!(products, (LAVA_PARTICLE_ID, WATER_PARTICLE_ID));
             }
         }
+
+        // Water + Lava reacts with a probability of 0.3 per step, rolling twice per pair would give about 0.51
+        let reaction_rate: f32 = reacted_pairs as f32 / pair_positions.len() as f32;
+        assert!((reaction_rate - 0.3).abs() < 0.07, "reaction rate was {reaction_rate}");
     }
 
-    fn handle_sands(&mut self, particle_index: usize) {
-        let direction_groups: Vec<Vec<Offset2<i32>>> = vec![
-            vec![Offset2::<i32> { dx: 0, dy: -1 }],
-            vec![Offset2::<i32> { dx: 1, dy: -1 }, Offset2::<i32> { dx: -1, dy: -1 }],
-        ];
+    #[test]
+    fn pairs_that_fail_to_react_fall_asleep() {
+        let mut renderer: NullRenderer = NullRenderer::new();
+        let mut engine: Engine = create_walled_engine(10, 10, &mut renderer);
+        engine.current_grid.create_particle_at(4, 5, SAND_PARTICLE_ID, true, true);
+        engine.current_grid.create_particle_at(5, 5, WATER_PARTICLE_ID, true, true);
+
+        for _ in 0..20 {
+            run_ticks(&mut engine, &mut renderer, 1);
+            if engine.current_grid.get_particle_at(4, 5).unwrap().id != SAND_PARTICLE_ID {
+                break;
+            }
+
+            // A failed roll doesn't keep the pair awake for another one
+            assert!(!engine.current_grid.has_dirty_particles());
+        }
+    }
+
+    #[test]
+    fn burning_wood_spreads_fire_and_burns_out_into_ash() {
+        let mut renderer: NullRenderer = NullRenderer::new();
+        let mut engine: Engine = create_walled_engine(10, 10, &mut renderer);
+        engine.current_grid.create_particle_at(4, 5, WOOD_PARTICLE_ID, true, true);
+        engine.current_grid.create_particle_at(5, 5, STONE_PARTICLE_ID, true, true);
+        engine.current_grid.create_particle_at(3, 5, OIL_PARTICLE_ID, true, true);
+
+        let wood_index: usize = 5 * 10 + 4;
+        let stone_index: usize = 5 * 10 + 5;
+        let oil_index: usize = 5 * 10 + 3;
+
+        // Light the wood the way a neighboring flame would
+        engine.current_grid.data[wood_index].burn_timer = 200;
+        engine.current_grid.mark_particle_dirty(4, 5, true);
+
+        // Wood burns for 200 steps, setting the oil next to it alight, but never the stone
+        let mut oil_ignited: bool = false;
+        for _ in 0..199 {
+            run_ticks(&mut engine, &mut renderer, 1);
+            oil_ignited |= engine.current_grid.data[oil_index].burn_timer > 0;
+            assert_eq!(engine.current_grid.data[stone_index].burn_timer, 0);
+        }
+        assert!(oil_ignited);
+        assert_eq!(engine.current_grid.data[wood_index].id, WOOD_PARTICLE_ID);
+        assert_eq!(engine.current_grid.data[wood_index].burn_timer, 1);
 
-        self.current_grid
-            .try_move_particle(particle_index, &direction_groups, true, true, true);
+        // Then it leaves its burn product behind
+        run_ticks(&mut engine, &mut renderer, 1);
+        assert_eq!(engine.current_grid.data[wood_index].id, VOLCANIC_ASH_PARTICLE_ID);
+        assert_eq!(engine.current_grid.data[wood_index].burn_timer, 0);
+        assert_eq!(engine.current_grid.data[stone_index].id, STONE_PARTICLE_ID);
     }
 }
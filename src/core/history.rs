@@ -0,0 +1,191 @@
+use crate::structs::grid::Grid;
+use crate::structs::particle::Particle;
+
+use std::collections::{HashSet, VecDeque};
+use std::mem::size_of;
+
+// A cell as it was before an edit touched it
+#[derive(Debug, Clone, Copy)]
+struct CellSnapshot {
+    index: u32,
+    particle: Particle,
+    temperature: f32,
+}
+
+impl CellSnapshot {
+    fn capture(grid: &Grid, index: u32) -> CellSnapshot {
+        return CellSnapshot {
+            index: index,
+            particle: grid.data[index as usize],
+            temperature: grid.temperature[index as usize],
+        };
+    }
+}
+
+// Every cell a single stroke changed, from mouse press to release
+#[derive(Debug, Default)]
+struct Stroke {
+    cells: Vec<CellSnapshot>,
+    // Only needed while recording, finished strokes drop it
+    recorded_indices: HashSet<u32>,
+    memory_size: usize,
+}
+
+impl Stroke {
+    fn record(&mut self, grid: &Grid, index: u32) {
+        // Only the first change matters, that is the state undo has to go back to
+        if !self.recorded_indices.insert(index) {
+            return;
+        }
+
+        self.cells.push(CellSnapshot::capture(grid, index));
+    }
+
+    // Frees what was only needed for recording, and measures what the stroke keeps taking up in the history
+    fn finish(mut self) -> Stroke {
+        self.recorded_indices = HashSet::new();
+        self.cells.shrink_to_fit();
+        self.memory_size = self.cells.capacity() * size_of::<CellSnapshot>();
+        return self;
+    }
+
+    // Writes the recorded cells back into the grid, returning a stroke that reverts this one
+    fn apply(self, grid: &mut Grid) -> Stroke {
+        let mut inverse: Stroke = Stroke::default();
+
+        for snapshot in self.cells {
+            inverse.record(grid, snapshot.index);
+
+            let x: i32 = snapshot.index as i32 % grid.width;
+            let y: i32 = snapshot.index as i32 / grid.width;
+            grid.data[snapshot.index as usize] = snapshot.particle;
+            grid.temperature[snapshot.index as usize] = snapshot.temperature;
+
+            // Restored cells have to be redrawn, and may have to start moving again
+            grid.mark_particle_dirty(x, y, true);
+        }
+
+        return inverse.finish();
+    }
+}
+
+// Undo and redo stacks of brush strokes, bounded by the memory they take up rather than by step count
+pub struct History {
+    undo_stack: VecDeque<Stroke>,
+    redo_stack: Vec<Stroke>,
+    current_stroke: Option<Stroke>,
+
+    memory_budget: usize,
+    memory_used: usize,
+}
+
+impl History {
+    pub fn new(memory_budget: usize) -> History {
+        return History {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            current_stroke: None,
+
+            memory_budget: memory_budget,
+            memory_used: 0,
+        };
+    }
+
+    pub fn is_stroke_active(&self) -> bool {
+        return self.current_stroke.is_some();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        return !self.undo_stack.is_empty();
+    }
+
+    pub fn can_redo(&self) -> bool {
+        return !self.redo_stack.is_empty();
+    }
+
+    pub fn get_memory_used(&self) -> usize {
+        return self.memory_used;
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.current_stroke = None;
+        self.memory_used = 0;
+    }
+
+    pub fn begin_stroke(&mut self) {
+        self.end_stroke();
+        self.current_stroke = Some(Stroke::default());
+    }
+
+    // Records a cell before the current stroke changes it
+    pub fn record_cell(&mut self, grid: &Grid, index: u32) {
+        if let Some(stroke) = self.current_stroke.as_mut() {
+            stroke.record(grid, index);
+        }
+    }
+
+    pub fn end_stroke(&mut self) {
+        let stroke: Stroke = match self.current_stroke.take() {
+            Some(val) => val,
+            None => return,
+        };
+
+        // Strokes that didn't touch anything aren't worth an undo step
+        if stroke.cells.is_empty() {
+            return;
+        }
+        let stroke: Stroke = stroke.finish();
+
+        // A new edit makes everything that was undone unreachable
+        for redo_stroke in self.redo_stack.drain(..) {
+            self.memory_used -= redo_stroke.memory_size;
+        }
+
+        self.memory_used += stroke.memory_size;
+        self.undo_stack.push_back(stroke);
+        self.enforce_budget();
+    }
+
+    pub fn undo(&mut self, grid: &mut Grid) -> bool {
+        self.end_stroke();
+
+        let stroke: Stroke = match self.undo_stack.pop_back() {
+            Some(val) => val,
+            None => return false,
+        };
+        self.memory_used -= stroke.memory_size;
+
+        let inverse: Stroke = stroke.apply(grid);
+        self.memory_used += inverse.memory_size;
+        self.redo_stack.push(inverse);
+        return true;
+    }
+
+    pub fn redo(&mut self, grid: &mut Grid) -> bool {
+        self.end_stroke();
+
+        let stroke: Stroke = match self.redo_stack.pop() {
+            Some(val) => val,
+            None => return false,
+        };
+        self.memory_used -= stroke.memory_size;
+
+        let inverse: Stroke = stroke.apply(grid);
+        self.memory_used += inverse.memory_size;
+        self.undo_stack.push_back(inverse);
+        self.enforce_budget();
+        return true;
+    }
+
+    // Forgets the oldest strokes until the history fits in its budget
+    fn enforce_budget(&mut self) {
+        while self.memory_used > self.memory_budget {
+            match self.undo_stack.pop_front() {
+                Some(stroke) => self.memory_used -= stroke.memory_size,
+                None => break,
+            }
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use winit::keyboard::{KeyCode, ModifiersState};
+
+// Number of particle slots reachable through the number keys
+pub const PARTICLE_SLOT_COUNT: u8 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    SelectParticleSlot(u8),
+    NextParticle,
+    PreviousParticle,
+    GrowBrush,
+    ShrinkBrush,
+    NextTool,
+    PreviousTool,
+    ToggleReplaceMask,
+    TogglePause,
+    Step,
+    SlowDown,
+    SpeedUp,
+    ResetSpeed,
+    RotateGravity,
+    ToggleZeroGravity,
+    Undo,
+    Redo,
+    SaveWorld,
+    LoadWorld,
+    Snapshot,
+}
+
+impl Action {
+    pub fn from_name(name: &str) -> Option<Action> {
+        if let Some(slot) = name.strip_prefix("select_particle_") {
+            return match slot.parse::<u8>() {
+                Ok(val) if (1..=PARTICLE_SLOT_COUNT).contains(&val) => Some(Action::SelectParticleSlot(val - 1)),
+                _ => None,
+            };
+        }
+
+        return match name {
+            "next_particle" => Some(Action::NextParticle),
+            "previous_particle" => Some(Action::PreviousParticle),
+            "grow_brush" => Some(Action::GrowBrush),
+            "shrink_brush" => Some(Action::ShrinkBrush),
+            "next_tool" => Some(Action::NextTool),
+            "previous_tool" => Some(Action::PreviousTool),
+            "toggle_replace_mask" => Some(Action::ToggleReplaceMask),
+            "toggle_pause" => Some(Action::TogglePause),
+            "step" => Some(Action::Step),
+            "slow_down" => Some(Action::SlowDown),
+            "speed_up" => Some(Action::SpeedUp),
+            "reset_speed" => Some(Action::ResetSpeed),
+            "rotate_gravity" => Some(Action::RotateGravity),
+            "toggle_zero_gravity" => Some(Action::ToggleZeroGravity),
+            "undo" => Some(Action::Undo),
+            "redo" => Some(Action::Redo),
+            "save_world" => Some(Action::SaveWorld),
+            "load_world" => Some(Action::LoadWorld),
+            "snapshot" => Some(Action::Snapshot),
+            _ => None,
+        };
+    }
+
+    // Actions that keep firing while their key is held down
+    pub fn is_repeatable(&self) -> bool {
+        return matches!(
+            self,
+            Action::NextParticle | Action::PreviousParticle | Action::GrowBrush | Action::ShrinkBrush | Action::Step | Action::Undo | Action::Redo
+        );
+    }
+}
+
+// A key together with the modifiers that have to be held for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub key_code: KeyCode,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl KeyBinding {
+    pub fn new(key_code: KeyCode) -> KeyBinding {
+        return KeyBinding {
+            key_code: key_code,
+            ctrl: false,
+            shift: false,
+            alt: false,
+        };
+    }
+
+    pub fn from_modifiers(key_code: KeyCode, modifiers: ModifiersState) -> KeyBinding {
+        return KeyBinding {
+            key_code: key_code,
+            ctrl: modifiers.control_key(),
+            shift: modifiers.shift_key(),
+            alt: modifiers.alt_key(),
+        };
+    }
+
+    // Parses bindings such as 'Space', or 'Ctrl+Shift+KeyZ', key names are winit's KeyCode names
+    pub fn parse(text: &str) -> Option<KeyBinding> {
+        let mut parts: Vec<&str> = text.split('+').map(|part| part.trim()).collect();
+        let key_code: KeyCode = parse_key_code(parts.pop()?)?;
+        let mut binding: KeyBinding = KeyBinding::new(key_code);
+
+        for modifier in parts {
+            match modifier {
+                "Ctrl" => binding.ctrl = true,
+                "Shift" => binding.shift = true,
+                "Alt" => binding.alt = true,
+                _ => return None,
+            }
+        }
+
+        return Some(binding);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyBindingDiagnostic {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for KeyBindingDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "line {}: {}", self.line, self.message);
+    }
+}
+
+pub struct KeyBindings {
+    bindings: HashMap<KeyBinding, Action>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> KeyBindings {
+        let digit_keys: [KeyCode; PARTICLE_SLOT_COUNT as usize] = [
+            KeyCode::Digit1,
+            KeyCode::Digit2,
+            KeyCode::Digit3,
+            KeyCode::Digit4,
+            KeyCode::Digit5,
+            KeyCode::Digit6,
+            KeyCode::Digit7,
+            KeyCode::Digit8,
+            KeyCode::Digit9,
+        ];
+
+        let mut bindings: HashMap<KeyBinding, Action> = HashMap::new();
+        for (slot, key_code) in digit_keys.into_iter().enumerate() {
+            bindings.insert(KeyBinding::new(key_code), Action::SelectParticleSlot(slot as u8));
+        }
+
+        let ctrl_z: KeyBinding = KeyBinding {
+            ctrl: true,
+            ..KeyBinding::new(KeyCode::KeyZ)
+        };
+        let ctrl_shift_z: KeyBinding = KeyBinding { shift: true, ..ctrl_z };
+        let ctrl_y: KeyBinding = KeyBinding {
+            ctrl: true,
+            ..KeyBinding::new(KeyCode::KeyY)
+        };
+        let shift_tab: KeyBinding = KeyBinding {
+            shift: true,
+            ..KeyBinding::new(KeyCode::Tab)
+        };
+        let shift_t: KeyBinding = KeyBinding {
+            shift: true,
+            ..KeyBinding::new(KeyCode::KeyT)
+        };
+        let shift_g: KeyBinding = KeyBinding {
+            shift: true,
+            ..KeyBinding::new(KeyCode::KeyG)
+        };
+
+        bindings.insert(KeyBinding::new(KeyCode::Tab), Action::NextParticle);
+        bindings.insert(shift_tab, Action::PreviousParticle);
+        bindings.insert(KeyBinding::new(KeyCode::BracketRight), Action::GrowBrush);
+        bindings.insert(KeyBinding::new(KeyCode::BracketLeft), Action::ShrinkBrush);
+        bindings.insert(KeyBinding::new(KeyCode::KeyT), Action::NextTool);
+        bindings.insert(shift_t, Action::PreviousTool);
+        bindings.insert(KeyBinding::new(KeyCode::KeyM), Action::ToggleReplaceMask);
+        bindings.insert(KeyBinding::new(KeyCode::Space), Action::TogglePause);
+        bindings.insert(KeyBinding::new(KeyCode::Period), Action::Step);
+        bindings.insert(KeyBinding::new(KeyCode::Minus), Action::SlowDown);
+        bindings.insert(KeyBinding::new(KeyCode::Equal), Action::SpeedUp);
+        bindings.insert(KeyBinding::new(KeyCode::Digit0), Action::ResetSpeed);
+        bindings.insert(KeyBinding::new(KeyCode::KeyG), Action::RotateGravity);
+        bindings.insert(shift_g, Action::ToggleZeroGravity);
+        bindings.insert(ctrl_z, Action::Undo);
+        bindings.insert(ctrl_shift_z, Action::Redo);
+        bindings.insert(ctrl_y, Action::Redo);
+        bindings.insert(KeyBinding::new(KeyCode::F5), Action::SaveWorld);
+        bindings.insert(KeyBinding::new(KeyCode::F9), Action::LoadWorld);
+        bindings.insert(KeyBinding::new(KeyCode::F12), Action::Snapshot);
+
+        return KeyBindings { bindings: bindings };
+    }
+}
+
+impl KeyBindings {
+    pub fn get_action(&self, binding: &KeyBinding) -> Option<Action> {
+        return self.bindings.get(binding).copied();
+    }
+
+    // Returns every key bound to an action
+    pub fn get_bindings_for(&self, action: Action) -> Vec<KeyBinding> {
+        return self.bindings.iter().filter(|(_, bound_action)| **bound_action == action).map(|(binding, _)| *binding).collect();
+    }
+
+    // Applies a bindings file on top of the defaults, an action listed in the file loses its default keys
+    // Lines that can't be understood are reported and skipped, so one typo doesn't lose every other binding
+    pub fn parse(text: &str) -> (KeyBindings, Vec<KeyBindingDiagnostic>) {
+        let mut key_bindings: KeyBindings = KeyBindings::default();
+        let mut diagnostics: Vec<KeyBindingDiagnostic> = Vec::new();
+        let mut remapped_actions: Vec<Action> = Vec::new();
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line_number: usize = line_index + 1;
+            let this_line: &str = line.trim();
+
+            // Ignore empty lines and lines starting with '#'
+            if this_line.is_empty() || this_line.starts_with('#') {
+                continue;
+            }
+
+            let mut report = |message: String| diagnostics.push(KeyBindingDiagnostic { line: line_number, message: message });
+            let (action_str, binding_str) = match this_line.split_once(':') {
+                Some(val) => val,
+                None => {
+                    report(format!("expected 'action: key', found '{}'", this_line));
+                    continue;
+                }
+            };
+            let action: Action = match Action::from_name(action_str.trim()) {
+                Some(val) => val,
+                None => {
+                    report(format!("unknown action '{}'", action_str.trim()));
+                    continue;
+                }
+            };
+            let binding: KeyBinding = match KeyBinding::parse(binding_str) {
+                Some(val) => val,
+                None => {
+                    report(format!("unknown key '{}'", binding_str.trim()));
+                    continue;
+                }
+            };
+
+            if !remapped_actions.contains(&action) {
+                remapped_actions.push(action);
+                key_bindings.bindings.retain(|_, bound_action| *bound_action != action);
+            }
+            key_bindings.bindings.insert(binding, action);
+        }
+
+        return (key_bindings, diagnostics);
+    }
+
+    pub fn load(path: &Path) -> Result<(KeyBindings, Vec<KeyBindingDiagnostic>), std::io::Error> {
+        let text: String = fs::read_to_string(path)?;
+        return Ok(Self::parse(&text));
+    }
+}
+
+// --------- Helper Functions ---------
+
+// Keys that can be named in a bindings file
+const BINDABLE_KEYS: [KeyCode; 71] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Backspace,
+    KeyCode::Delete,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Backslash,
+    KeyCode::Backquote,
+    KeyCode::Home,
+    KeyCode::End,
+];
+
+fn parse_key_code(name: &str) -> Option<KeyCode> {
+    return BINDABLE_KEYS.into_iter().find(|key_code| format!("{:?}", key_code) == name);
+}
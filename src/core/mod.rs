@@ -1,4 +1,9 @@
 pub mod app_manager_interface;
 pub mod engine;
+pub mod history;
+pub mod key_bindings;
 pub mod native_app_manager;
+pub mod physics;
+pub mod timestep;
+pub mod tools;
 pub mod web_app_manager;
@@ -1,19 +1,23 @@
 use crate::core::app_manager_interface::AppManagerInterface;
 use crate::core::engine::Engine;
+use crate::core::key_bindings::{Action, KeyBinding, KeyBindings};
+use crate::core::timestep::FixedTimestep;
 use crate::io::native_renderer::NativeRenderer;
 use crate::io::renderer_interface::RendererInterface;
+use crate::io::snapshot::{SnapshotError, next_snapshot_path};
 use crate::settings::Settings;
-use crate::structs::input_state;
 use crate::structs::input_state::InputState;
 use crate::structs::utils::Vector2;
 
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use winit::application::ApplicationHandler;
 use winit::dpi::{LogicalPosition, LogicalSize, PhysicalSize};
-use winit::event::{ElementState, MouseButton, WindowEvent};
+use winit::event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent};
 use winit::event_loop::EventLoop;
 use winit::event_loop::{ActiveEventLoop, ControlFlow};
+use winit::keyboard::{KeyCode, ModifiersState, PhysicalKey};
 use winit::window::{Window, WindowAttributes, WindowId};
 
 pub struct NativeAppManager {
@@ -24,25 +28,46 @@ pub struct NativeAppManager {
     pub engine: Option<Engine>,
 
     input_state: InputState,
+    modifiers: ModifiersState,
+    key_bindings: KeyBindings,
+    scroll_accumulator: f64,
+    timestep: FixedTimestep,
     last_frame_time: Instant,
+    last_update_time: Instant,
 }
 
 impl AppManagerInterface for NativeAppManager {
     fn new() -> NativeAppManager {
-        let input_state: InputState = InputState {
-            mouse_position: Vector2::<f64>::new(0.0, 0.0),
-            mouse_left_down: false,
-            mouse_right_down: false,
-        };
+        let input_state: InputState = InputState::default();
+
+        // Command line arguments may add particle data, like a mod directory
+        let mut settings: Settings = Settings::default();
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        if let Err(error) = settings.apply_args(&args) {
+            eprintln!("{}", error);
+            std::process::exit(2);
+        }
+        let key_bindings: KeyBindings = Self::load_key_bindings(&settings.key_bindings_path);
+
+        let mut timestep: FixedTimestep = FixedTimestep::new(
+            Duration::from_millis(settings.physics_update_interval),
+            settings.max_physics_ticks_per_frame,
+        );
+        timestep.set_time_scale(settings.time_scale);
 
         return NativeAppManager {
-            settings: Settings::default(),
+            settings: settings,
             window: None,
             renderer: None,
             engine: None,
 
             input_state: input_state,
+            modifiers: ModifiersState::empty(),
+            key_bindings: key_bindings,
+            scroll_accumulator: 0.0,
+            timestep: timestep,
             last_frame_time: Instant::now(),
+            last_update_time: Instant::now(),
         };
     }
 
@@ -52,6 +77,132 @@ impl AppManagerInterface for NativeAppManager {
     }
 }
 
+impl NativeAppManager {
+    // Saves the current frame as a PNG in the snapshot directory
+    fn take_snapshot(&self) -> Result<PathBuf, SnapshotError> {
+        let renderer: &NativeRenderer = self.renderer.as_ref().ok_or(SnapshotError::NoFrame)?;
+        let path: PathBuf = next_snapshot_path(&self.settings.snapshot_directory)?;
+        renderer.save_snapshot(&path)?;
+        return Ok(path);
+    }
+
+    fn handle_key_press(&mut self, key_code: KeyCode, repeat: bool) {
+        let binding: KeyBinding = KeyBinding::from_modifiers(key_code, self.modifiers);
+        let action: Action = match self.key_bindings.get_action(&binding) {
+            Some(val) => val,
+            None => return,
+        };
+
+        // Holding a key down only repeats actions where that makes sense
+        if repeat && !action.is_repeatable() {
+            return;
+        }
+
+        let (Some(engine), Some(renderer)) = (&mut self.engine, &mut self.renderer) else {
+            return;
+        };
+
+        match action {
+            Action::SelectParticleSlot(slot) => {
+                if engine.select_particle_slot(slot as usize) {
+                    Self::print_selected_particle(engine);
+                }
+            }
+            Action::NextParticle => {
+                engine.cycle_selected_particle(1);
+                Self::print_selected_particle(engine);
+            }
+            Action::PreviousParticle => {
+                engine.cycle_selected_particle(-1);
+                Self::print_selected_particle(engine);
+            }
+            Action::GrowBrush => engine.resize_brush(1),
+            Action::ShrinkBrush => engine.resize_brush(-1),
+            Action::NextTool => {
+                engine.cycle_active_tool(1);
+                println!("Selected {}", engine.get_active_tool_name());
+            }
+            Action::PreviousTool => {
+                engine.cycle_active_tool(-1);
+                println!("Selected {}", engine.get_active_tool_name());
+            }
+            Action::ToggleReplaceMask => {
+                let pos: &Vector2<f64> = &self.input_state.mouse_position;
+                engine.toggle_replace_mask_at(pos.x.floor() as i32, pos.y.floor() as i32);
+                match engine.replace_mask.and_then(|mask_id| engine.current_grid.get_particle_data(mask_id)) {
+                    Some(particle_data) => println!("Only painting over {}", particle_data.name),
+                    None => println!("Painting over everything"),
+                }
+            }
+            Action::TogglePause => {
+                engine.toggle_pause();
+                println!("{}", if engine.is_paused { "Paused" } else { "Resumed" });
+            }
+            Action::Step => engine.request_step(),
+            Action::SlowDown => {
+                self.timestep.set_time_scale(self.timestep.get_time_scale() / 2.0);
+                println!("Simulation speed {}x", self.timestep.get_time_scale());
+            }
+            Action::SpeedUp => {
+                self.timestep.set_time_scale(self.timestep.get_time_scale() * 2.0);
+                println!("Simulation speed {}x", self.timestep.get_time_scale());
+            }
+            Action::ResetSpeed => {
+                self.timestep.set_time_scale(1.0);
+                println!("Simulation speed 1x");
+            }
+            Action::RotateGravity => {
+                engine.rotate_gravity();
+                println!("{}", engine.current_grid.get_gravity());
+            }
+            Action::ToggleZeroGravity => {
+                engine.toggle_zero_gravity();
+                println!("{}", engine.current_grid.get_gravity());
+            }
+            Action::Undo => {
+                engine.undo();
+            }
+            Action::Redo => {
+                engine.redo();
+            }
+            Action::SaveWorld => match engine.save_world(&self.settings.world_save_path) {
+                Ok(()) => println!("Saved world to {}", self.settings.world_save_path.display()),
+                Err(error) => eprintln!("Failed to save world: {}", error),
+            },
+            Action::LoadWorld => match engine.load_world(&self.settings.world_save_path, renderer) {
+                Ok(()) => println!("Loaded world from {}", self.settings.world_save_path.display()),
+                Err(error) => eprintln!("Failed to load world: {}", error),
+            },
+            Action::Snapshot => match self.take_snapshot() {
+                Ok(path) => println!("Saved snapshot to {}", path.display()),
+                Err(error) => eprintln!("Failed to save snapshot: {}", error),
+            },
+        }
+    }
+
+    fn print_selected_particle(engine: &Engine) {
+        if let Some(particle_data) = engine.current_grid.get_particle_data(engine.selected_particle_id) {
+            println!("Selected {}", particle_data.name);
+        }
+    }
+
+    fn load_key_bindings(path: &Path) -> KeyBindings {
+        match KeyBindings::load(path) {
+            Ok((key_bindings, diagnostics)) => {
+                for diagnostic in diagnostics {
+                    eprintln!("{}: {}", path.display(), diagnostic);
+                }
+                return key_bindings;
+            }
+            Err(error) => {
+                // Missing bindings aren't fatal, the defaults are perfectly usable
+                eprintln!("Could not read key bindings from {} ({}), using defaults", path.display(), error);
+                return KeyBindings::default();
+            }
+        }
+    }
+}
+
 impl ApplicationHandler for NativeAppManager {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         let window_width: f64 = self.settings.game_width as f64 * 4.0;
@@ -80,23 +231,38 @@ impl ApplicationHandler for NativeAppManager {
         let renderer: NativeRenderer = NativeRenderer::new(self.settings.game_width, self.settings.game_height, &window);
         self.renderer = Some(renderer);
 
-        let engine = Engine::new(
-            self.settings.game_width,
-            self.settings.game_height,
-            self.renderer.as_mut().expect("Renderer not initialized"),
-        );
+        let engine = match Engine::new(&self.settings, self.renderer.as_mut().expect("Renderer not initialized")) {
+            Ok(val) => val,
+            Err(error) => {
+                // Particle data is broken, tell the user what's wrong and bail
+                eprintln!("{}", error);
+                event_loop.exit();
+                return;
+            }
+        };
+        println!("Seed {}", engine.seed);
+        if !engine.particle_data_conflicts.is_empty() {
+            eprint!("{}", engine.particle_data_conflicts);
+        }
         self.engine = Some(engine);
 
+        // Setting up took a while, that time shouldn't count towards the first frame's ticks
+        self.timestep.reset();
+        self.last_update_time = Instant::now();
+
         // Request first draw
         window.request_redraw();
     }
 
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, window_id: WindowId, event: WindowEvent) {
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _window_id: WindowId, event: WindowEvent) {
         match event {
             WindowEvent::RedrawRequested => {
                 if let (Some(engine), Some(renderer)) = (&mut self.engine, &mut self.renderer) {
-                    // Update physics
-                    engine.update(renderer, &self.input_state);
+                    // Run however many physics ticks the time since the last frame is worth
+                    let now: Instant = Instant::now();
+                    let ticks: u32 = self.timestep.advance(now - self.last_update_time);
+                    self.last_update_time = now;
+                    engine.run_frame(renderer, &mut self.input_state, ticks);
 
                     // Render this frame
                     renderer.render_frame();
@@ -108,10 +274,20 @@ impl ApplicationHandler for NativeAppManager {
                     renderer.resize(new_size.width, new_size.height);
                 }
             }
+            WindowEvent::DroppedFile(path) => {
+                // Dropping a PNG onto the window loads it as a level
+                if let (Some(engine), Some(renderer)) = (&mut self.engine, &mut self.renderer) {
+                    let legend_path: Option<&Path> = self.settings.level_legend_path.as_deref();
+                    match engine.import_level(&path, legend_path, self.settings.level_fit_mode, renderer) {
+                        Ok(()) => println!("Imported level from {}", path.display()),
+                        Err(error) => eprintln!("Failed to import level: {}", error),
+                    }
+                }
+            }
             WindowEvent::CloseRequested => {
                 event_loop.exit();
             }
-            WindowEvent::CursorMoved { device_id, position } => {
+            WindowEvent::CursorMoved { device_id: _, position } => {
                 let window_size: winit::dpi::PhysicalSize<u32> = self.window.as_ref().unwrap().inner_size();
                 let scale_x: f64 = self.settings.game_width as f64 / window_size.width as f64;
                 let scale_y: f64 = self.settings.game_height as f64 / window_size.height as f64;
@@ -121,7 +297,36 @@ impl ApplicationHandler for NativeAppManager {
                 self.input_state.mouse_position.x = position.x * scale_x;
                 self.input_state.mouse_position.y = flipped_y * scale_y;
             }
-            WindowEvent::MouseInput { device_id, state, button } => {
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(key_code),
+                        state: ElementState::Pressed,
+                        repeat,
+                        ..
+                    },
+                ..
+            } => {
+                self.handle_key_press(key_code, repeat);
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                // Scrolling up grows the brush, one line or roughly one line's worth of pixels per step
+                self.scroll_accumulator += match delta {
+                    MouseScrollDelta::LineDelta(_, y) => y as f64,
+                    MouseScrollDelta::PixelDelta(position) => position.y / 20.0,
+                };
+
+                // Trackpads scroll in small increments, keep the remainder for the next event
+                let steps: f64 = self.scroll_accumulator.trunc();
+                self.scroll_accumulator -= steps;
+                if let Some(engine) = &mut self.engine {
+                    engine.resize_brush(steps as i32);
+                }
+            }
+            WindowEvent::MouseInput { device_id: _, state, button } => {
                 let is_down: bool = state == ElementState::Pressed;
 
                 match button {
@@ -134,7 +339,7 @@ impl ApplicationHandler for NativeAppManager {
         }
     }
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
-        let target_frame_time = Duration::from_secs_f64(1.0 / 60.0); // 60 FPS
+        let target_frame_time: Duration = Duration::from_millis(self.settings.render_update_interval);
         let now = Instant::now();
         let next_frame_time = self.last_frame_time + target_frame_time;
 
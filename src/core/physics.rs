@@ -0,0 +1,568 @@
+use crate::structs::chunk::MAX_PARTICLE_REACH;
+use crate::structs::gravity::Gravity;
+use crate::structs::grid::{MOORE_NEIGHBORS, VON_NEUMANN_NEIGHBORS};
+use crate::structs::grid_cells::GridCells;
+use crate::structs::particle::{PARTICLE_FLAG_SCORCHED, Particle, TERMINAL_VELOCITY};
+use crate::structs::reaction::Reaction;
+use crate::structs::utils::{Offset2, Vector2};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+const EMPTY_PARTICLE_ID: u16 = 0;
+const SMOKE_PARTICLE_ID: u16 = 200;
+const FIRE_PARTICLE_ID: u16 = 400;
+const MAX_TOPPLE_STEPS: i32 = 8;
+
+// Liquids spread at most this far in one step, so they never reach past what a chunk update may touch
+const MAX_DISPERSION: i32 = MAX_PARTICLE_REACH - 1;
+const _: () = assert!(MAX_TOPPLE_STEPS < MAX_PARTICLE_REACH);
+
+// Chances per step for a burning particle to give off flames, and smoke
+const FLAME_CHANCE: f32 = 0.1;
+const SMOKE_CHANCE: f32 = 0.03;
+const FLAME_FLICKER_CHANCE: f32 = 0.3;
+const BURNING_TEMPERATURE: f32 = 600.0;
+
+// Falling particles speed up by this many cells per tick, every tick, up to the terminal velocity
+// Gravity's strength scales the acceleration, but not the terminal velocity
+const GRAVITY_ACCELERATION: f32 = 0.2;
+const _: () = assert!((TERMINAL_VELOCITY as i32) < MAX_PARTICLE_REACH);
+
+// Sinking through anything but empty space is no faster than a cell per tick
+const SINKING_VELOCITY: f32 = 1.0;
+
+// Particles landing at least this fast turn part of their speed sideways, liquids more than sand
+const SPLASH_MIN_SPEED: f32 = 2.0;
+const LIQUID_SPLASH_TRANSFER: f32 = 0.6;
+const SAND_SPLASH_TRANSFER: f32 = 0.25;
+
+// Share of the sideways speed kept after every tick spent sliding
+const LIQUID_SLIDE_FRICTION: f32 = 0.8;
+const SAND_SLIDE_FRICTION: f32 = 0.5;
+
+// Without gravity loose particles wander about, this is the chance per step to take a random step
+const DRIFT_CHANCE: f32 = 0.2;
+
+// Every chunk draws from its own RNG, so the result doesn't depend on which thread gets to it first
+pub fn create_chunk_rng(tick_seed: u64, chunk_index: usize) -> StdRng {
+    let chunk_seed: u64 = tick_seed ^ (chunk_index as u64 + 1).wrapping_mul(0x9E3779B97F4A7C15);
+    return StdRng::seed_from_u64(chunk_seed);
+}
+
+// Moves, burns and reacts the dirty particles of a single chunk
+pub struct ChunkUpdater<'a> {
+    cells: GridCells<'a>,
+    rng: StdRng,
+    gravity: Gravity,
+}
+
+impl<'a> ChunkUpdater<'a> {
+    pub fn new(cells: GridCells<'a>, rng: StdRng, gravity: Gravity) -> ChunkUpdater<'a> {
+        return ChunkUpdater {
+            cells: cells,
+            rng: rng,
+            gravity: gravity,
+        };
+    }
+
+    // Takes the chunk's dirty cells, paired with the category each cell held when the tick started
+    // Remembering the category means a cell something moved into this tick isn't updated a second time
+    pub fn update(&mut self, cells_to_update: &mut [(u32, u16)]) {
+        // Shuffle to randomize horizontal order
+        cells_to_update.shuffle(&mut self.rng);
+
+        // Sort from the bottom up, as seen by gravity
+        let width: u32 = self.cells.width as u32;
+        let gravity: Gravity = self.gravity;
+        cells_to_update.sort_by_key(|&(index, _)| gravity.get_height((index % width) as i32, (index / width) as i32));
+
+        for &(index, category) in cells_to_update.iter() {
+            let particle_index: usize = index as usize;
+
+            // Particles that reacted have been replaced, don't move them this step
+            if self.try_react(particle_index) {
+                continue;
+            }
+
+            // Burning particles that burnt out have been replaced as well
+            if self.cells.particle(particle_index).burn_timer > 0 && self.handle_burning(particle_index) {
+                continue;
+            }
+
+            match category {
+                2 => self.handle_liquids(particle_index),
+                3 => self.handle_gases(particle_index),
+                5 => self.handle_flames(particle_index),
+                4 => self.handle_sands(particle_index),
+                _ => {}
+            }
+        }
+    }
+
+    fn try_react(&mut self, particle_index: usize) -> bool {
+        let position: Vector2<i32> = self.cells.get_position(particle_index);
+        let (x, y, particle_id) = (position.x, position.y, self.cells.particle(particle_index).id);
+
+        // Check Von Neumann neighbors in random order so reactions have no directional bias
+        let mut neighbor_indices: Vec<u32> = self.cells.get_neighbor_indices_of(x, y, &VON_NEUMANN_NEIGHBORS);
+        neighbor_indices.shuffle(&mut self.rng);
+
+        for neighbor_index in neighbor_indices {
+            // Each pair rolls once per step, from the particle with the lower index
+            if (neighbor_index as usize) < particle_index {
+                continue;
+            }
+
+            let neighbor_position: Vector2<i32> = self.cells.get_position(neighbor_index as usize);
+            let neighbor_id: u16 = self.cells.particle(neighbor_index as usize).id;
+            let (neighbor_x, neighbor_y) = (neighbor_position.x, neighbor_position.y);
+
+            let reaction: Reaction = match self.cells.get_registry().find_reaction(particle_id, neighbor_id) {
+                Some(val) => val,
+                None => continue,
+            };
+
+            if self.rng.random::<f32>() < reaction.probability {
+                self.cells.create_particle_at(&mut self.rng, x, y, reaction.product_a, true, true);
+                self.cells
+                    .create_particle_at(&mut self.rng, neighbor_x, neighbor_y, reaction.product_b, true, true);
+                return true;
+            }
+        }
+
+        return false;
+    }
+
+    // Returns true if the particle burnt out and was replaced
+    fn handle_burning(&mut self, particle_index: usize) -> bool {
+        let position: Vector2<i32> = self.cells.get_position(particle_index);
+        let particle: &mut Particle = self.cells.particle_mut(particle_index);
+        particle.burn_timer -= 1;
+        let (x, y, particle_id, burn_timer) = (position.x, position.y, particle.id, particle.burn_timer);
+        let category: u16 = self.cells.get_properties(particle_id).category;
+
+        // Leave the burn product behind once the particle burnt out
+        if burn_timer == 0 {
+            let burn_into: u16 = self.cells.get_particle_data(particle_id).map_or(EMPTY_PARTICLE_ID, |data| data.burn_into);
+            self.cells.create_particle_at(&mut self.rng, x, y, burn_into, true, true);
+            return true;
+        }
+
+        // Keep the fire awake, and hot
+        self.cells.mark_particle_dirty(x, y, false);
+        let temperature: f32 = self.cells.get_temperature(particle_index);
+        self.cells.set_temperature(particle_index, temperature.max(BURNING_TEMPERATURE));
+
+        // Spread to flammable neighbors
+        for neighbor_index in self.cells.get_neighbor_indices_of(x, y, &MOORE_NEIGHBORS) {
+            let neighbor_index: usize = neighbor_index as usize;
+            let neighbor: &Particle = self.cells.particle(neighbor_index);
+            if neighbor.burn_timer > 0 {
+                continue;
+            }
+
+            let flammability: f32 = self.cells.get_particle_data(neighbor.id).map_or(0.0, |data| data.flammability);
+            if flammability > 0.0 && self.rng.random::<f32>() < flammability {
+                self.ignite(neighbor_index);
+            }
+        }
+
+        // Give off flames (unless this already is a flame), and smoke into the empty space above
+        let up: Offset2<i32> = self.gravity.orient(Offset2::<i32>::new(0, 1));
+        let (above_x, above_y) = (x + up.dx, y + up.dy);
+        let above_is_empty: bool = self.cells.get_particle_at(above_x, above_y).is_some_and(|above| above.id == EMPTY_PARTICLE_ID);
+        if above_is_empty {
+            if category != 5 && self.rng.random::<f32>() < FLAME_CHANCE {
+                self.cells.create_particle_at(&mut self.rng, above_x, above_y, FIRE_PARTICLE_ID, true, true);
+            } else if self.rng.random::<f32>() < SMOKE_CHANCE {
+                self.cells.create_particle_at(&mut self.rng, above_x, above_y, SMOKE_PARTICLE_ID, true, true);
+            }
+        }
+
+        return false;
+    }
+
+    fn ignite(&mut self, particle_index: usize) {
+        let particle_id: u16 = self.cells.particle(particle_index).id;
+        let burn_time: u32 = self.cells.get_particle_data(particle_id).map_or(0, |data| data.burn_time);
+
+        // Start the burn timer, and tint the particle with the flame's color
+        let particle: &mut Particle = self.cells.particle_mut(particle_index);
+        particle.burn_timer = burn_time.max(1);
+        particle.flags |= PARTICLE_FLAG_SCORCHED;
+
+        let position: Vector2<i32> = self.cells.get_position(particle_index);
+        self.cells.mark_particle_dirty(position.x, position.y, true);
+    }
+
+    // Velocities are stored the way the world sees them, the handlers read and write them as if gravity pulled down
+    fn get_velocity(&self, particle_index: usize) -> Vector2<f32> {
+        return self.gravity.unorient_velocity(self.cells.particle(particle_index).get_velocity());
+    }
+
+    fn set_velocity(&mut self, particle_index: usize, velocity: Vector2<f32>) {
+        self.cells.particle_mut(particle_index).set_velocity(self.gravity.orient_velocity(velocity));
+    }
+
+    // Returns true if the particle fell, a particle that's stopped short lands
+    fn fall(&mut self, particle_index: usize) -> bool {
+        let down: Offset2<i32> = self.gravity.orient(Offset2::<i32>::new(0, -1));
+        let mut velocity: Vector2<f32> = self.get_velocity(particle_index);
+        velocity.y = (velocity.y - GRAVITY_ACCELERATION * self.gravity.strength).max(-TERMINAL_VELOCITY);
+        self.set_velocity(particle_index, velocity);
+
+        // Trace the whole drop, so nothing falls through a floor thinner than its speed
+        let distance: i32 = ((-velocity.y).ceil() as i32).max(1);
+        let (end_index, fallen) = self.cells.try_move_particle_along(
+            particle_index,
+            Offset2::<i32>::new(down.dx * distance, down.dy * distance),
+            true,
+            true,
+        );
+
+        if fallen < distance {
+            // The trace ends early inside whatever the particle is heavier than, from there it sinks slowly
+            let end_position: Vector2<i32> = self.cells.get_position(end_index);
+            if fallen > 0 && self.cells.can_displace(end_index, end_position.x + down.dx, end_position.y + down.dy) {
+                velocity.y = velocity.y.max(-SINKING_VELOCITY);
+                self.set_velocity(end_index, velocity);
+            } else {
+                self.land(end_index, -velocity.y);
+            }
+        }
+
+        return fallen > 0;
+    }
+
+    fn land(&mut self, particle_index: usize, speed: f32) {
+        let category: u16 = self.cells.get_properties(self.cells.particle(particle_index).id).category;
+        let transfer: f32 = if category == 2 { LIQUID_SPLASH_TRANSFER } else { SAND_SPLASH_TRANSFER };
+
+        // Hard landings splash sideways, keeping the direction the particle was already heading in
+        let splash_sign: f32 = if self.rng.random_bool(0.5) { 1.0 } else { -1.0 };
+        let mut velocity: Vector2<f32> = self.get_velocity(particle_index);
+        velocity.y = 0.0;
+        if speed < SPLASH_MIN_SPEED {
+            self.set_velocity(particle_index, velocity);
+            return;
+        }
+
+        let sign: f32 = if velocity.x != 0.0 { velocity.x.signum() } else { splash_sign };
+        velocity.x = sign * (speed * transfer).min(TERMINAL_VELOCITY);
+        self.set_velocity(particle_index, velocity);
+
+        // Stay awake to slide off next step
+        let position: Vector2<i32> = self.cells.get_position(particle_index);
+        self.cells.mark_particle_dirty(position.x, position.y, false);
+    }
+
+    // Returns true if the particle slid, running into something stops it dead
+    fn slide(&mut self, particle_index: usize) -> bool {
+        let mut velocity: Vector2<f32> = self.get_velocity(particle_index);
+        if velocity.x.abs() < 1.0 {
+            velocity.x = 0.0;
+            self.set_velocity(particle_index, velocity);
+            return false;
+        }
+
+        let friction: f32 = if self.cells.get_properties(self.cells.particle(particle_index).id).category == 2 {
+            LIQUID_SLIDE_FRICTION
+        } else {
+            SAND_SLIDE_FRICTION
+        };
+
+        let distance: i32 = velocity.x.trunc() as i32;
+        let (end_index, slid) = self
+            .cells
+            .try_move_particle_along(particle_index, self.gravity.orient(Offset2::<i32>::new(distance, 0)), true, true);
+
+        velocity.x = if slid < distance.abs() { 0.0 } else { velocity.x * friction };
+        self.set_velocity(end_index, velocity);
+
+        return slid > 0;
+    }
+
+    // Without gravity nothing settles, particles coast along until they hit something and otherwise wander about
+    fn drift(&mut self, particle_index: usize) {
+        let velocity: Vector2<f32> = self.cells.particle(particle_index).get_velocity();
+        if velocity.x.abs() >= 1.0 || velocity.y.abs() >= 1.0 {
+            let offset: Offset2<i32> = Offset2::<i32>::new(velocity.x.trunc() as i32, velocity.y.trunc() as i32);
+            let (end_index, moved) = self.cells.try_move_particle_along(particle_index, offset, true, true);
+
+            // Bumping into anything stops the particle dead
+            if moved < offset.dx.abs().max(offset.dy.abs()) {
+                self.cells.particle_mut(end_index).set_velocity(Vector2::<f32>::new(0.0, 0.0));
+            }
+            return;
+        }
+
+        // Stay awake to keep wandering
+        let position: Vector2<i32> = self.cells.get_position(particle_index);
+        self.cells.mark_particle_dirty(position.x, position.y, false);
+
+        if self.rng.random::<f32>() < DRIFT_CHANCE {
+            let mut drift_group: Vec<Offset2<i32>> = MOORE_NEIGHBORS.iter().map(|&(dx, dy)| Offset2::<i32>::new(dx, dy)).collect();
+            drift_group.shuffle(&mut self.rng);
+            self.cells.try_move_particle(particle_index, &[drift_group], true, true);
+        }
+    }
+
+    // One step to the side and its mirror, in random order
+    fn get_sideways_pair(&mut self, dy: i32) -> Vec<Offset2<i32>> {
+        let first_dx: i32 = if self.rng.random_bool(0.5) { 1 } else { -1 };
+        return vec![
+            self.gravity.orient(Offset2::<i32>::new(first_dx, dy)),
+            self.gravity.orient(Offset2::<i32>::new(-first_dx, dy)),
+        ];
+    }
+
+    fn handle_sands(&mut self, particle_index: usize) {
+        if self.gravity.is_zero() {
+            self.drift(particle_index);
+            return;
+        }
+
+        // Fall whenever possible, then slide off whatever speed the landing left
+        if self.fall(particle_index) || self.slide(particle_index) {
+            return;
+        }
+
+        // Otherwise topple sideways, but only if the pile is steeper than the material's repose angle
+        let particle_id: u16 = self.cells.particle(particle_index).id;
+        let repose_angle: f32 = match self.cells.get_particle_data(particle_id) {
+            Some(data) => data.repose_angle,
+            None => return,
+        };
+        let (rise, run) = Self::get_topple_step(repose_angle, &mut self.rng);
+
+        let first_dx: i32 = if self.rng.random_bool(0.5) { 1 } else { -1 };
+        for dx in [first_dx, -first_dx] {
+            if self.can_topple(particle_index, dx, rise, run) {
+                let topple_group: Vec<Vec<Offset2<i32>>> = vec![vec![self.gravity.orient(Offset2::<i32>::new(dx * run, -1))]];
+                self.cells.try_move_particle(particle_index, &topple_group, true, true);
+                return;
+            }
+        }
+    }
+
+    // Converts a repose angle into the drop (rise) and sideways distance (run) a grain needs before toppling
+    fn get_topple_step(repose_angle: f32, rng: &mut StdRng) -> (i32, i32) {
+        let slope: f32 = repose_angle.clamp(1.0, 89.0).to_radians().tan();
+
+        // Fractional slopes are rounded randomly so piles average out to the exact angle
+        let mut round_randomly = |value: f32| -> i32 {
+            let whole: f32 = value.floor();
+            let extra: i32 = if rng.random::<f32>() < value - whole { 1 } else { 0 };
+            return (whole as i32 + extra).clamp(1, MAX_TOPPLE_STEPS);
+        };
+
+        if slope >= 1.0 {
+            return (round_randomly(slope), 1);
+        } else {
+            return (1, round_randomly(1.0 / slope));
+        }
+    }
+
+    fn can_topple(&self, particle_index: usize, dx: i32, rise: i32, run: i32) -> bool {
+        let position: Vector2<i32> = self.cells.get_position(particle_index);
+
+        // The grain has to be able to slide along the surface...
+        for step in 1..run {
+            let offset: Offset2<i32> = self.gravity.orient(Offset2::<i32>::new(dx * step, 0));
+            if !self.cells.can_displace(particle_index, position.x + offset.dx, position.y + offset.dy) {
+                return false;
+            }
+        }
+
+        // ...and then find a drop at least as deep as the rise below its landing spot
+        for depth in 1..=rise {
+            let offset: Offset2<i32> = self.gravity.orient(Offset2::<i32>::new(dx * run, -depth));
+            if !self.cells.can_displace(particle_index, position.x + offset.dx, position.y + offset.dy) {
+                return false;
+            }
+        }
+
+        return true;
+    }
+
+    fn handle_liquids(&mut self, particle_index: usize) {
+        if self.gravity.is_zero() {
+            self.drift(particle_index);
+            return;
+        }
+
+        // Fall straight down first, and slide along with any splash
+        if self.fall(particle_index) || self.slide(particle_index) {
+            return;
+        }
+
+        // Then flow down diagonally
+        let direction_groups: Vec<Vec<Offset2<i32>>> = vec![self.get_sideways_pair(-1)];
+        if self.cells.try_move_particle(particle_index, &direction_groups, true, true) {
+            return;
+        }
+
+        // Can't fall any further, spread sideways by up to the liquid's dispersion rate
+        let particle_id: u16 = self.cells.particle(particle_index).id;
+        let dispersion: i32 = match self.cells.get_particle_data(particle_id) {
+            Some(data) => (data.dispersion as i32).min(MAX_DISPERSION),
+            None => return,
+        };
+
+        let first_dx: i32 = if self.rng.random_bool(0.5) { 1 } else { -1 };
+        for dx in [first_dx, -first_dx] {
+            let distance: i32 = self
+                .cells
+                .get_free_distance(particle_index, self.gravity.orient(Offset2::<i32>::new(dx, 0)), dispersion);
+
+            if distance > 0 {
+                let spread_group: Vec<Vec<Offset2<i32>>> = vec![vec![self.gravity.orient(Offset2::<i32>::new(dx * distance, 0))]];
+                self.cells.try_move_particle(particle_index, &spread_group, true, true);
+                return;
+            }
+        }
+    }
+
+    fn handle_gases(&mut self, particle_index: usize) {
+        // Age the gas by one tick
+        let position: Vector2<i32> = self.cells.get_position(particle_index);
+        let particle: &mut Particle = self.cells.particle_mut(particle_index);
+        particle.age += 1;
+        let (x, y, particle_id, age) = (position.x, position.y, particle.id, particle.age);
+
+        // Dissipate the gas back into empty space once it outlives its lifetime (0 lives forever)
+        let lifetime: u32 = match self.cells.get_particle_data(particle_id) {
+            Some(data) => data.lifetime,
+            None => return,
+        };
+        if lifetime > 0 && age >= lifetime {
+            self.cells.create_particle_at(&mut self.rng, x, y, EMPTY_PARTICLE_ID, true, true);
+            return;
+        }
+
+        // Keep the gas awake so it keeps aging even when it can't move
+        self.cells.mark_particle_dirty(x, y, false);
+
+        self.rise_and_diffuse(particle_index);
+    }
+
+    fn handle_flames(&mut self, particle_index: usize) {
+        // Flames linger around whatever they're burning, only flickering upwards every now and then
+        if self.rng.random::<f32>() < FLAME_FLICKER_CHANCE {
+            self.rise_and_diffuse(particle_index);
+        }
+    }
+
+    fn rise_and_diffuse(&mut self, particle_index: usize) {
+        // With nothing to rise against, just spread out evenly
+        if self.gravity.is_zero() {
+            let mut diffuse_group: Vec<Offset2<i32>> =
+                VON_NEUMANN_NEIGHBORS.iter().map(|&(dx, dy)| Offset2::<i32>::new(dx, dy)).collect();
+            diffuse_group.shuffle(&mut self.rng);
+            self.cells.try_move_particle(particle_index, &[diffuse_group], true, true);
+            return;
+        }
+
+        // Rise against gravity, sometimes drifting sideways first to diffuse
+        let rise_group: Vec<Offset2<i32>> = vec![self.gravity.orient(Offset2::<i32>::new(0, 1))];
+        let rise_diagonal_group: Vec<Offset2<i32>> = self.get_sideways_pair(1);
+        let drift_group: Vec<Offset2<i32>> = self.get_sideways_pair(0);
+
+        let direction_groups: Vec<Vec<Offset2<i32>>> = if self.rng.random_bool(0.3) {
+            vec![drift_group, rise_group, rise_diagonal_group]
+        } else {
+            vec![rise_group, rise_diagonal_group, drift_group]
+        };
+
+        self.cells.try_move_particle(particle_index, &direction_groups, true, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::load_particle_data;
+    use crate::settings::Settings;
+    use crate::structs::grid::Grid;
+    use crate::structs::particle_registry::ParticleRegistry;
+
+    const GRAVEL_PARTICLE_ID: u16 = 303;
+
+    const GRID_WIDTH: u16 = 200;
+    const GRID_HEIGHT: u16 = 150;
+    const GRAIN_COUNT: usize = 1500;
+
+    // Runs one physics tick over the grid's dirty cells, without an engine around it
+    fn step(grid: &mut Grid) {
+        let mut cells_to_update: Vec<(u32, u16)> = grid
+            .take_dirty_indices()
+            .into_iter()
+            .map(|index| (index, grid.get_properties(grid.data[index as usize].id).category))
+            .collect();
+        let gravity: Gravity = grid.get_gravity();
+        let (cells, rng) = grid.cells();
+        let chunk_rng: StdRng = StdRng::seed_from_u64(rng.random());
+        ChunkUpdater::new(cells, chunk_rng, gravity).update(&mut cells_to_update);
+    }
+
+    // Pours gravel with the given repose angle onto a single spot and returns the angle of the resulting pile's flanks in degrees
+    fn measure_pile_angle(repose_angle: f32, seed: u64) -> f32 {
+        let registry_path: String = Settings::default().particle_data_paths[0].display().to_string();
+        let mut registry: ParticleRegistry = load_particle_data(&registry_path).unwrap();
+        registry.particles.get_mut(&GRAVEL_PARTICLE_ID).unwrap().repose_angle = repose_angle;
+
+        let mut grid: Grid = Grid::new(GRID_WIDTH, GRID_HEIGHT, registry, seed);
+        grid.populate(EMPTY_PARTICLE_ID);
+        let particle_id: u16 = GRAVEL_PARTICLE_ID;
+
+        let spawn_x: i32 = GRID_WIDTH as i32 / 2;
+        let spawn_y: i32 = GRID_HEIGHT as i32 - 1;
+        for _ in 0..GRAIN_COUNT {
+            grid.create_particle_at(spawn_x, spawn_y, particle_id, true, true);
+            step(&mut grid);
+        }
+
+        // Let the pile settle
+        for _ in 0..10_000 {
+            if !grid.has_dirty_particles() {
+                break;
+            }
+            step(&mut grid);
+        }
+
+        // Measure column heights
+        let heights: Vec<f32> = (0..grid.width)
+            .map(|x| (0..grid.height).filter(|&y| grid.get_particle_at(x, y).unwrap().id == particle_id).count() as f32)
+            .collect();
+        let peak: f32 = heights.iter().cloned().fold(0.0, f32::max);
+
+        // Measure each flank between a quarter and three quarters of the peak to skip the rounded tip and foot
+        let left_columns: Vec<usize> = (0..heights.len()).collect();
+        let right_columns: Vec<usize> = (0..heights.len()).rev().collect();
+        let mut flank_angles: Vec<f32> = Vec::new();
+        for columns in [left_columns, right_columns] {
+            let find_crossing = |level: f32| -> f32 { columns.iter().find(|&&x| heights[x] >= level).cloned().unwrap() as f32 };
+            let low_x: f32 = find_crossing(peak * 0.25);
+            let high_x: f32 = find_crossing(peak * 0.75);
+            flank_angles.push((peak * 0.5 / (high_x - low_x).abs().max(1.0)).atan().to_degrees());
+        }
+
+        return flank_angles.iter().sum::<f32>() / flank_angles.len() as f32;
+    }
+
+    #[test]
+    fn repose_angle_shapes_pile_slope() {
+        let shallow_angle: f32 = measure_pile_angle(30.0, 5);
+        let steeper_angle: f32 = measure_pile_angle(40.0, 5);
+        let steep_angle: f32 = measure_pile_angle(65.0, 5);
+
+        assert!((shallow_angle - 30.0).abs() < 4.0, "30 degree pile angle was {shallow_angle}");
+        assert!((steeper_angle - 40.0).abs() < 4.0, "40 degree pile angle was {steeper_angle}");
+        assert!((steep_angle - 65.0).abs() < 4.0, "65 degree pile angle was {steep_angle}");
+
+        // Ten degrees apart has to show up in the pile, ignoring the angle would give both the same slope
+        assert!(steeper_angle - shallow_angle > 5.0, "{shallow_angle} vs {steeper_angle}");
+    }
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+// Slowest and fastest the simulation can be played back at
+pub const MIN_TIME_SCALE: f64 = 0.125;
+pub const MAX_TIME_SCALE: f64 = 8.0;
+
+// Turns real time into a whole number of fixed length physics ticks, so the simulation runs at the same
+// speed no matter how fast frames are drawn
+pub struct FixedTimestep {
+    tick_interval: Duration,
+    max_ticks_per_frame: u32,
+    time_scale: f64,
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    pub fn new(tick_interval: Duration, max_ticks_per_frame: u32) -> FixedTimestep {
+        return FixedTimestep {
+            // A zero interval would ask for infinitely many ticks
+            tick_interval: tick_interval.max(Duration::from_millis(1)),
+            max_ticks_per_frame: max_ticks_per_frame.max(1),
+            time_scale: 1.0,
+            accumulator: Duration::ZERO,
+        };
+    }
+
+    pub fn get_tick_interval(&self) -> Duration {
+        return self.tick_interval;
+    }
+
+    pub fn get_time_scale(&self) -> f64 {
+        return self.time_scale;
+    }
+
+    // Below one is slow motion, above one is fast forward
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.time_scale = time_scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+    }
+
+    // Time that has passed but isn't worth a whole tick yet
+    pub fn get_accumulated_time(&self) -> Duration {
+        return self.accumulator;
+    }
+
+    // Adds the real time since the last frame and returns how many ticks to run for it
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed.mul_f64(self.time_scale);
+
+        // Fast forward is due more ticks every frame, so the cap grows with it and only limits the backlog
+        let max_ticks: u32 = (self.max_ticks_per_frame as f64 * self.time_scale.max(1.0)).ceil() as u32;
+        let due_ticks: u128 = self.accumulator.as_nanos() / self.tick_interval.as_nanos();
+        if due_ticks > max_ticks as u128 {
+            // Too far behind to catch up (a slow frame, or the window was dragged), drop the backlog
+            // instead of running ever more ticks per frame and falling further behind
+            self.accumulator = Duration::ZERO;
+            return max_ticks;
+        }
+
+        let ticks: u32 = due_ticks as u32;
+        self.accumulator -= self.tick_interval * ticks;
+        return ticks;
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::ZERO;
+    }
+}
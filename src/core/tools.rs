@@ -0,0 +1,170 @@
+use crate::structs::grid::Grid;
+use crate::structs::utils::Vector2;
+
+use rand::Rng;
+use std::collections::HashSet;
+
+// Where in a stroke a tool is being applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolPhase {
+    Press,
+    Drag,
+    Release,
+}
+
+// A stroke from mouse press to release, positions are in grid cells
+#[derive(Debug, Clone, Copy)]
+pub struct ToolStroke {
+    pub start: Vector2<i32>,
+    pub previous: Vector2<i32>,
+    pub current: Vector2<i32>,
+    pub particle_id: u16,
+    pub brush_radius: i32,
+}
+
+// Tools decide which cells a stroke paints, the engine does the painting so history and masks apply to every tool
+// The grid is only mutable so tools can draw from its RNG
+pub trait Tool {
+    fn get_name(&self) -> &'static str;
+    fn apply(&mut self, grid: &mut Grid, stroke: &ToolStroke, phase: ToolPhase) -> Vec<u32>;
+}
+
+// --------- Brushes ---------
+
+// Stamps a brush along the path from the previous to the current position, spaced by the brush radius
+// The previous position was stamped during the last update, so only the press stamps where the stroke starts
+fn stamp_along_stroke(stroke: &ToolStroke, phase: ToolPhase, mut stamp: impl FnMut(i32, i32) -> Vec<u32>) -> Vec<u32> {
+    let dx: f32 = (stroke.current.x - stroke.previous.x) as f32;
+    let dy: f32 = (stroke.current.y - stroke.previous.y) as f32;
+    let spacing: f32 = stroke.brush_radius.max(1) as f32;
+    let stamp_count: i32 = ((dx * dx + dy * dy).sqrt() / spacing).ceil() as i32;
+
+    let mut stamped: HashSet<u32> = HashSet::new();
+    let mut indices: Vec<u32> = Vec::new();
+    let first_stamp: i32 = if phase == ToolPhase::Press { 0 } else { 1 };
+    for step in first_stamp..=stamp_count.max(first_stamp) {
+        let t: f32 = if stamp_count == 0 { 1.0 } else { step as f32 / stamp_count as f32 };
+        let x: i32 = (stroke.previous.x as f32 + dx * t).round() as i32;
+        let y: i32 = (stroke.previous.y as f32 + dy * t).round() as i32;
+
+        // Overlapping stamps would paint the same cell more than once
+        indices.extend(stamp(x, y).into_iter().filter(|&index| stamped.insert(index)));
+    }
+
+    return indices;
+}
+
+// Paints a filled circle under the cursor for as long as the mouse is held
+pub struct CircleBrush;
+
+impl Tool for CircleBrush {
+    fn get_name(&self) -> &'static str {
+        return "Circle Brush";
+    }
+
+    fn apply(&mut self, grid: &mut Grid, stroke: &ToolStroke, phase: ToolPhase) -> Vec<u32> {
+        if phase == ToolPhase::Release {
+            return Vec::new();
+        }
+        return stamp_along_stroke(stroke, phase, |x, y| grid.get_circle_indices(x, y, stroke.brush_radius));
+    }
+}
+
+// Paints a filled square under the cursor for as long as the mouse is held
+pub struct SquareBrush;
+
+impl Tool for SquareBrush {
+    fn get_name(&self) -> &'static str {
+        return "Square Brush";
+    }
+
+    fn apply(&mut self, grid: &mut Grid, stroke: &ToolStroke, phase: ToolPhase) -> Vec<u32> {
+        if phase == ToolPhase::Release {
+            return Vec::new();
+        }
+        return stamp_along_stroke(stroke, phase, |x, y| grid.get_square_indices(x, y, stroke.brush_radius));
+    }
+}
+
+// Scatters particles over the brush area, density is the chance for each cell to be painted per step
+pub struct SprayBrush {
+    pub density: f32,
+}
+
+impl Tool for SprayBrush {
+    fn get_name(&self) -> &'static str {
+        return "Spray Brush";
+    }
+
+    fn apply(&mut self, grid: &mut Grid, stroke: &ToolStroke, phase: ToolPhase) -> Vec<u32> {
+        if phase == ToolPhase::Release {
+            return Vec::new();
+        }
+
+        let mut indices: Vec<u32> = stamp_along_stroke(stroke, phase, |x, y| grid.get_circle_indices(x, y, stroke.brush_radius));
+        indices.retain(|_| grid.rng.random::<f32>() < self.density);
+        return indices;
+    }
+}
+
+// --------- Shapes ---------
+
+// Draws a one cell wide line from where the drag started to where it ended
+pub struct LineTool;
+
+impl Tool for LineTool {
+    fn get_name(&self) -> &'static str {
+        return "Line";
+    }
+
+    fn apply(&mut self, grid: &mut Grid, stroke: &ToolStroke, phase: ToolPhase) -> Vec<u32> {
+        if phase != ToolPhase::Release {
+            return Vec::new();
+        }
+        return grid.get_line_indices(stroke.start.x, stroke.start.y, stroke.current.x, stroke.current.y);
+    }
+}
+
+// Fills the rectangle spanned by the drag's start and end
+pub struct RectangleTool;
+
+impl Tool for RectangleTool {
+    fn get_name(&self) -> &'static str {
+        return "Rectangle";
+    }
+
+    fn apply(&mut self, grid: &mut Grid, stroke: &ToolStroke, phase: ToolPhase) -> Vec<u32> {
+        if phase != ToolPhase::Release {
+            return Vec::new();
+        }
+        return grid.get_rect_indices(stroke.start.x, stroke.start.y, stroke.current.x, stroke.current.y);
+    }
+}
+
+// Fills the connected region of the same particle under the cursor
+pub struct FloodFillTool;
+
+impl Tool for FloodFillTool {
+    fn get_name(&self) -> &'static str {
+        return "Flood Fill";
+    }
+
+    fn apply(&mut self, grid: &mut Grid, stroke: &ToolStroke, phase: ToolPhase) -> Vec<u32> {
+        if phase != ToolPhase::Press {
+            return Vec::new();
+        }
+        return grid.get_connected_indices(stroke.start.x, stroke.start.y);
+    }
+}
+
+// Tools available in the engine, in the order they are cycled through
+pub fn create_default_tools() -> Vec<Box<dyn Tool>> {
+    return vec![
+        Box::new(CircleBrush),
+        Box::new(SquareBrush),
+        Box::new(SprayBrush { density: 0.1 }),
+        Box::new(LineTool),
+        Box::new(RectangleTool),
+        Box::new(FloodFillTool),
+    ];
+}
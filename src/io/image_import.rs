@@ -0,0 +1,221 @@
+use crate::structs::color::Color;
+use crate::structs::particle::COLOR_VARIANT_COUNT;
+use crate::structs::particle_registry::ParticleRegistry;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::path::Path;
+
+const EMPTY_PARTICLE_ID: u16 = 0;
+
+// Pixels more transparent than this are left empty
+const ALPHA_THRESHOLD: u8 = 128;
+
+// How an image that doesn't match the grid size is fitted onto it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FitMode {
+    // Stretch the image over the whole grid
+    Scale,
+    // Keep pixels one to one, anchored to the bottom left corner of the grid
+    Crop,
+}
+
+#[derive(Debug)]
+pub enum ImageImportError {
+    Io(std::io::Error),
+    Decoding(png::DecodingError),
+    UnsupportedFormat(String),
+    MalformedLegendLine { line: usize, text: String },
+    UnknownParticleId { line: usize, id: u16 },
+    EmptyLegend,
+}
+
+impl fmt::Display for ImageImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageImportError::Io(error) => write!(f, "could not read file ({})", error),
+            ImageImportError::Decoding(error) => write!(f, "could not decode image ({})", error),
+            ImageImportError::UnsupportedFormat(format) => write!(f, "unsupported image format ({})", format),
+            ImageImportError::MalformedLegendLine { line, text } => {
+                write!(f, "legend line {}: expected 'id: #RRGGBB', found '{}'", line, text)
+            }
+            ImageImportError::UnknownParticleId { line, id } => write!(f, "legend line {}: unknown particle ID {}", line, id),
+            ImageImportError::EmptyLegend => write!(f, "legend has no entries"),
+        }
+    }
+}
+
+impl std::error::Error for ImageImportError {}
+
+impl From<std::io::Error> for ImageImportError {
+    fn from(error: std::io::Error) -> ImageImportError {
+        return ImageImportError::Io(error);
+    }
+}
+
+impl From<png::DecodingError> for ImageImportError {
+    fn from(error: png::DecodingError) -> ImageImportError {
+        return ImageImportError::Decoding(error);
+    }
+}
+
+// --------- Palette ---------
+
+// Colors and the particle each one stands for
+pub struct Palette {
+    entries: Vec<(Color, u16)>,
+}
+
+impl Palette {
+    // Builds a palette from every color a particle in the registry can be drawn with
+    pub fn from_registry(registry: &ParticleRegistry) -> Palette {
+        let mut particle_ids: Vec<&u16> = registry.particles.keys().collect();
+        particle_ids.sort();
+
+        let steps: f32 = (COLOR_VARIANT_COUNT - 1) as f32;
+        let mut entries: Vec<(Color, u16)> = Vec::new();
+        for id in particle_ids {
+            let particle_data = &registry.particles[id];
+            for variant in 0..COLOR_VARIANT_COUNT {
+                let t: f32 = variant as f32 / steps;
+                entries.push((Color::lerp_hex(&particle_data.base_color, &particle_data.variant_color, t), *id));
+            }
+        }
+
+        return Palette { entries: entries };
+    }
+
+    // Reads a legend file, each line maps a particle ID to a color as 'id: #RRGGBB', '#' starts a comment
+    pub fn from_legend_file(path: &Path, registry: &ParticleRegistry) -> Result<Palette, ImageImportError> {
+        let text: String = fs::read_to_string(path)?;
+        return Self::from_legend(&text, registry);
+    }
+
+    pub fn from_legend(text: &str, registry: &ParticleRegistry) -> Result<Palette, ImageImportError> {
+        let mut entries: Vec<(Color, u16)> = Vec::new();
+
+        for (line_index, line) in text.lines().enumerate() {
+            let line_number: usize = line_index + 1;
+            let this_line: &str = line.trim();
+
+            // Ignore empty lines and lines starting with '#'
+            if this_line.is_empty() || this_line.starts_with('#') {
+                continue;
+            }
+
+            let malformed = || ImageImportError::MalformedLegendLine {
+                line: line_number,
+                text: this_line.to_string(),
+            };
+            let (id_str, color_str) = this_line.split_once(':').ok_or_else(malformed)?;
+            let id: u16 = id_str.trim().parse::<u16>().map_err(|_| malformed())?;
+            let color_str: &str = color_str.trim();
+            let hex_digits: &str = color_str.strip_prefix('#').unwrap_or(color_str);
+            if hex_digits.len() != 6 || !hex_digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(malformed());
+            }
+            if registry.get(id).is_none() {
+                return Err(ImageImportError::UnknownParticleId { line: line_number, id: id });
+            }
+
+            entries.push((Color::from_hex(color_str), id));
+        }
+
+        if entries.is_empty() {
+            return Err(ImageImportError::EmptyLegend);
+        }
+
+        return Ok(Palette { entries: entries });
+    }
+
+    // Returns the particle whose color is closest to the given one
+    pub fn match_color(&self, color: Color) -> u16 {
+        let mut best_id: u16 = EMPTY_PARTICLE_ID;
+        let mut best_distance: i32 = i32::MAX;
+
+        for (entry_color, id) in &self.entries {
+            let dr: i32 = entry_color.r as i32 - color.r as i32;
+            let dg: i32 = entry_color.g as i32 - color.g as i32;
+            let db: i32 = entry_color.b as i32 - color.b as i32;
+            let distance: i32 = dr * dr + dg * dg + db * db;
+
+            if distance < best_distance {
+                best_distance = distance;
+                best_id = *id;
+            }
+        }
+
+        return best_id;
+    }
+}
+
+// --------- Import ---------
+
+// Decodes a PNG into RGBA pixels, top row first
+fn decode_png(path: &Path) -> Result<(usize, usize, Vec<u8>), ImageImportError> {
+    let mut decoder: png::Decoder<File> = png::Decoder::new(File::open(path)?);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+    let mut reader: png::Reader<File> = decoder.read_info()?;
+    let mut buffer: Vec<u8> = vec![0; reader.output_buffer_size()];
+    let info: png::OutputInfo = reader.next_frame(&mut buffer)?;
+    let width: usize = info.width as usize;
+    let height: usize = info.height as usize;
+
+    // Widen whatever channel layout we got into RGBA
+    let mut pixels: Vec<u8> = Vec::with_capacity(width * height * 4);
+    for row in buffer[..info.buffer_size()].chunks_exact(info.line_size) {
+        match info.color_type {
+            png::ColorType::Rgba => pixels.extend_from_slice(&row[..width * 4]),
+            png::ColorType::Rgb => row[..width * 3].chunks_exact(3).for_each(|p| pixels.extend_from_slice(&[p[0], p[1], p[2], 255])),
+            png::ColorType::GrayscaleAlpha => row[..width * 2].chunks_exact(2).for_each(|p| pixels.extend_from_slice(&[p[0], p[0], p[0], p[1]])),
+            png::ColorType::Grayscale => row[..width].iter().for_each(|&v| pixels.extend_from_slice(&[v, v, v, 255])),
+            png::ColorType::Indexed => return Err(ImageImportError::UnsupportedFormat("unexpanded indexed color".to_string())),
+        }
+    }
+
+    return Ok((width, height, pixels));
+}
+
+// Loads a PNG and maps every pixel to a particle ID, returned in grid order (row 0 is the bottom row)
+pub fn import_image(
+    path: &Path,
+    palette: &Palette,
+    grid_width: usize,
+    grid_height: usize,
+    fit_mode: FitMode,
+) -> Result<Vec<u16>, ImageImportError> {
+    let (image_width, image_height, pixels) = decode_png(path)?;
+    let mut particle_ids: Vec<u16> = vec![EMPTY_PARTICLE_ID; grid_width * grid_height];
+
+    // Images tend to use only a handful of colors, so remember what each one matched
+    let mut matched_colors: HashMap<[u8; 4], u16> = HashMap::new();
+
+    for y in 0..grid_height {
+        for x in 0..grid_width {
+            // Pick the image pixel for this cell, counting image rows from the bottom
+            let (image_x, image_row_from_bottom) = match fit_mode {
+                FitMode::Scale => (x * image_width / grid_width, y * image_height / grid_height),
+                FitMode::Crop => (x, y),
+            };
+            if image_x >= image_width || image_row_from_bottom >= image_height {
+                continue;
+            }
+            let image_y: usize = image_height - 1 - image_row_from_bottom;
+
+            let offset: usize = (image_y * image_width + image_x) * 4;
+            let rgba: [u8; 4] = [pixels[offset], pixels[offset + 1], pixels[offset + 2], pixels[offset + 3]];
+            if rgba[3] < ALPHA_THRESHOLD {
+                continue;
+            }
+
+            let particle_id: u16 = *matched_colors
+                .entry(rgba)
+                .or_insert_with(|| palette.match_color(Color::from_rgba(rgba[0], rgba[1], rgba[2], rgba[3])));
+            particle_ids[y * grid_width + x] = particle_id;
+        }
+    }
+
+    return Ok(particle_ids);
+}
@@ -1,4 +1,9 @@
+pub mod image_import;
 pub mod native_renderer;
+pub mod null_renderer;
 pub mod renderer_interface;
 pub mod renderer_utils;
+pub mod snapshot;
+pub mod software_renderer;
 pub mod web_renderer;
+pub mod world_file;
@@ -1,7 +1,6 @@
 use crate::io::renderer_interface::RendererInterface;
 use crate::io::renderer_utils::*;
-use crate::structs::color::Color;
-use crate::structs::particle::Particle;
+use crate::io::snapshot::Snapshot;
 use crate::structs::utils::Pixel;
 
 use pollster::block_on;
@@ -24,6 +23,7 @@ pub struct NativeRenderer {
     width: usize,
     height: usize,
     frame_buffer: Vec<u8>,
+    overlay_buffer: Vec<u8>,
     dirty_rects: Vec<DirtyRect>,
 
     // queued_particles: Vec<Particle>,
@@ -46,7 +46,7 @@ impl NativeRenderer {
 
         // Initialise WGPU variables
         let instance: wgpu::Instance = wgpu::Instance::default();
-        let surface: Surface<'static> = unsafe { instance.create_surface(leaked_window) }.expect("Failed to create surface");
+        let surface: Surface<'static> = instance.create_surface(leaked_window).expect("Failed to create surface");
         let adapter: wgpu::Adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
             compatible_surface: Some(&surface),
             power_preference: wgpu::PowerPreference::HighPerformance,
@@ -84,7 +84,7 @@ impl NativeRenderer {
         let base_group: RenderGroup = create_base_render_group(width as u32, height as u32, &device, wgpu::TextureFormat::Bgra8UnormSrgb);
 
         // Initialise effects, and debug overlay layers with transparent textures
-        let transparent_buffer = vec![0u8; (width * height * 4) as usize];
+        let transparent_buffer = vec![0u8; width * height * 4];
         for layer_index in 1..=2 {
             queue.write_texture(
                 wgpu::ImageCopyTexture {
@@ -115,6 +115,7 @@ impl NativeRenderer {
             width: width,
             height: height,
             frame_buffer: vec![0; width * height * 4],
+            overlay_buffer: vec![0; width * height * 4],
             dirty_rects: Vec::new(),
             // queued_particles: Vec::new(),
             debug_overlay_pixels: HashMap::new(),
@@ -194,7 +195,7 @@ impl NativeRenderer {
 }
 impl RendererInterface for NativeRenderer {
     // Queues a batch of particles to be processed and rendered later
-    fn queue_particles(&mut self, particles_to_queue: &[Particle]) {
+    fn queue_particles(&mut self, particles_to_queue: &[Pixel]) {
         // There are no particles to queue, return
         if particles_to_queue.is_empty() {
             return;
@@ -203,16 +204,13 @@ impl RendererInterface for NativeRenderer {
         let width = self.width as u32;
 
         for particle in particles_to_queue {
-            let index: usize = particle.index as usize;
-
-            // ! Todo: make a function that computes final color or sum shader logic or sumn
-            let final_color: Color = particle.color;
+            let index: usize = particle.index;
 
             let offset = index * 4;
-            self.frame_buffer[offset + 0] = final_color.b;
-            self.frame_buffer[offset + 1] = final_color.g;
-            self.frame_buffer[offset + 2] = final_color.r;
-            self.frame_buffer[offset + 3] = final_color.a;
+            self.frame_buffer[offset + 0] = particle.b;
+            self.frame_buffer[offset + 1] = particle.g;
+            self.frame_buffer[offset + 2] = particle.r;
+            self.frame_buffer[offset + 3] = particle.a;
 
             let x = (index as u32) % width;
             let y = (index as u32) / width;
@@ -281,6 +279,38 @@ impl RendererInterface for NativeRenderer {
             );
         }
 
+        // Upload any queued debug overlay pixels
+        if !self.debug_overlay_pixels.is_empty() {
+            for (index, pixel) in self.debug_overlay_pixels.drain() {
+                // Write pixel to overlay buffer in BGRA format
+                let offset = index * 4;
+                self.overlay_buffer[offset + 0] = pixel.b;
+                self.overlay_buffer[offset + 1] = pixel.g;
+                self.overlay_buffer[offset + 2] = pixel.r;
+                self.overlay_buffer[offset + 3] = pixel.a;
+            }
+
+            self.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &self.base_group.layers[2].texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &self.overlay_buffer,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * self.width as u32),
+                    rows_per_image: Some(self.height as u32),
+                },
+                wgpu::Extent3d {
+                    width: self.width as u32,
+                    height: self.height as u32,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
         // Get a new swapchain frame
         let frame: wgpu::SurfaceTexture = match self.surface.get_current_texture() {
             Ok(val) => val,
@@ -354,4 +384,17 @@ impl RendererInterface for NativeRenderer {
         self.queue.submit(Some(encoder.finish()));
         frame.present();
     }
+
+    // Composites the CPU side copies of the layers, reading back the GPU surface isn't worth the stall
+    fn capture_frame(&self) -> Option<Snapshot> {
+        let mut pixels: Vec<u8> = vec![0; self.width * self.height * 4];
+        let layers: [&[u8]; 2] = [&self.frame_buffer, &self.overlay_buffer];
+        composite_layers(&layers, self.width, self.height, &mut pixels);
+
+        return Some(Snapshot {
+            width: self.width,
+            height: self.height,
+            pixels: pixels,
+        });
+    }
 }
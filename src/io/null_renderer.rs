@@ -0,0 +1,27 @@
+use crate::io::renderer_interface::RendererInterface;
+use crate::structs::utils::Pixel;
+
+// Renderer that draws nothing, lets the engine run headless in tests and CI without a window or GPU
+#[derive(Debug, Default)]
+pub struct NullRenderer {
+    pub frames_rendered: u64,
+    pub particles_queued: u64,
+}
+
+impl NullRenderer {
+    pub fn new() -> NullRenderer {
+        return NullRenderer::default();
+    }
+}
+
+impl RendererInterface for NullRenderer {
+    fn queue_particles(&mut self, particles_to_queue: &[Pixel]) {
+        self.particles_queued += particles_to_queue.len() as u64;
+    }
+
+    fn queue_debug_overlay_pixels(&mut self, _pixels_to_queue: Vec<Pixel>) {}
+
+    fn render_frame(&mut self) {
+        self.frames_rendered += 1;
+    }
+}
@@ -1,8 +1,20 @@
-use crate::structs::particle::Particle;
+use crate::io::snapshot::{Snapshot, SnapshotError};
 use crate::structs::utils::Pixel;
 
+use std::path::Path;
+
 pub trait RendererInterface {
-    fn queue_particles(&mut self, particles_to_queue: &[Particle]);
+    fn queue_particles(&mut self, particles_to_queue: &[Pixel]);
     fn queue_debug_overlay_pixels(&mut self, pixels_to_queue: Vec<Pixel>);
     fn render_frame(&mut self);
+
+    // Returns the composited frame, renderers that don't keep one return None
+    fn capture_frame(&self) -> Option<Snapshot> {
+        return None;
+    }
+
+    fn save_snapshot(&self, path: &Path) -> Result<(), SnapshotError> {
+        let snapshot: Snapshot = self.capture_frame().ok_or(SnapshotError::NoFrame)?;
+        return snapshot.write_png(path);
+    }
 }
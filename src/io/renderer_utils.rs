@@ -1,5 +1,9 @@
-use crate::structs::color::Color;
-use crate::structs::particle::Particle;
+use crate::structs::utils::Pixel;
+
+use std::sync::OnceLock;
+
+// Layers below this alpha are skipped, same as the composite shader
+const ALPHA_CUTOFF: f32 = 0.001;
 
 // ------ Structs ------
 pub struct RenderLayer {
@@ -264,24 +268,95 @@ pub fn create_base_render_group(width: u32, height: u32, device: &wgpu::Device,
 //     };
 // }
 
-pub fn process_particles(particles: &[Particle], frame_buffer: &mut [u8], width: usize) {
+pub fn process_particles(particles: &[Pixel], frame_buffer: &mut [u8]) {
     if particles.is_empty() {
         return;
     }
 
     for particle in particles {
-        let x: usize = particle.position.x as usize;
-        let y: usize = particle.position.y as usize;
-        let index: usize = y * width + x;
+        // Write pixel to frame buffer in BGRA format
+        let offset = particle.index * 4;
+        frame_buffer[offset + 0] = particle.b;
+        frame_buffer[offset + 1] = particle.g;
+        frame_buffer[offset + 2] = particle.r;
+        frame_buffer[offset + 3] = particle.a;
+    }
+}
 
-        // ! Todo: make a function that computes final color or sum shader logic or sumn
-        let final_color: Color = particle.color;
+// Composites BGRA layers bottom to top the same way base_composite.wgsl does, into an RGBA image
+// Layers are laid out like the grid (row 0 is the bottom row), output is top row first as it appears on screen
+pub fn composite_layers(layers: &[&[u8]], width: usize, height: usize, output: &mut [u8]) {
+    for y in 0..height {
+        // Flip rows so the bottom of the grid ends up at the bottom of the image
+        let output_row: usize = height - 1 - y;
 
-        // Write pixel to frame buffer in BGRA format
-        let offset = index * 4;
-        frame_buffer[offset + 0] = final_color.b;
-        frame_buffer[offset + 1] = final_color.g;
-        frame_buffer[offset + 2] = final_color.r;
-        frame_buffer[offset + 3] = final_color.a;
+        for x in 0..width {
+            let offset: usize = (y * width + x) * 4;
+
+            let mut out_color: [f32; 4] = [0.0; 4];
+            for (layer_index, layer) in layers.iter().enumerate() {
+                let layer_color: [f32; 4] = sample_bgra(layer, offset);
+
+                // The base layer is always drawn, the ones above only where they aren't transparent
+                if layer_index == 0 {
+                    out_color = layer_color;
+                } else if layer_color[3] > ALPHA_CUTOFF {
+                    out_color = alpha_blend(out_color, layer_color);
+                }
+            }
+
+            // Write pixel to output buffer in RGBA format
+            let output_offset: usize = (output_row * width + x) * 4;
+            output[output_offset + 0] = encode_srgb(out_color[0]);
+            output[output_offset + 1] = encode_srgb(out_color[1]);
+            output[output_offset + 2] = encode_srgb(out_color[2]);
+            output[output_offset + 3] = (out_color[3].clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
     }
 }
+
+// Reads a BGRA texel as linear RGBA, layers are sampled as sRGB textures on the GPU
+fn sample_bgra(buffer: &[u8], offset: usize) -> [f32; 4] {
+    static SRGB_TO_LINEAR: OnceLock<[f32; 256]> = OnceLock::new();
+    let srgb_to_linear: &[f32; 256] = SRGB_TO_LINEAR.get_or_init(|| {
+        let mut table: [f32; 256] = [0.0; 256];
+        for (value, linear) in table.iter_mut().enumerate() {
+            *linear = decode_srgb(value as f32 / 255.0);
+        }
+        return table;
+    });
+
+    return [
+        srgb_to_linear[buffer[offset + 2] as usize],
+        srgb_to_linear[buffer[offset + 1] as usize],
+        srgb_to_linear[buffer[offset + 0] as usize],
+        buffer[offset + 3] as f32 / 255.0,
+    ];
+}
+
+fn decode_srgb(value: f32) -> f32 {
+    if value <= 0.04045 {
+        return value / 12.92;
+    }
+    return ((value + 0.055) / 1.055).powf(2.4);
+}
+
+fn encode_srgb(value: f32) -> u8 {
+    let value: f32 = value.clamp(0.0, 1.0);
+    let encoded: f32 = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    return (encoded * 255.0).round() as u8;
+}
+
+fn alpha_blend(base: [f32; 4], over: [f32; 4]) -> [f32; 4] {
+    let inv_alpha: f32 = 1.0 - over[3];
+    return [
+        over[0] * over[3] + base[0] * inv_alpha,
+        over[1] * over[3] + base[1] * inv_alpha,
+        over[2] * over[3] + base[2] * inv_alpha,
+        over[3] + base[3] * inv_alpha,
+    ];
+}
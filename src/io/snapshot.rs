@@ -0,0 +1,67 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// A composited frame in RGBA format, top row first
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    NoFrame,
+    Io(std::io::Error),
+    Encoding(png::EncodingError),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::NoFrame => write!(f, "renderer has no frame to capture"),
+            SnapshotError::Io(error) => write!(f, "could not write snapshot ({})", error),
+            SnapshotError::Encoding(error) => write!(f, "could not encode snapshot ({})", error),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(error: std::io::Error) -> SnapshotError {
+        return SnapshotError::Io(error);
+    }
+}
+
+impl From<png::EncodingError> for SnapshotError {
+    fn from(error: png::EncodingError) -> SnapshotError {
+        return SnapshotError::Encoding(error);
+    }
+}
+
+impl Snapshot {
+    pub fn write_png(&self, path: &Path) -> Result<(), SnapshotError> {
+        let file: File = File::create(path)?;
+        let mut encoder: png::Encoder<BufWriter<File>> = png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+
+        let mut writer: png::Writer<BufWriter<File>> = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        return Ok(());
+    }
+}
+
+// Builds a file path for a new snapshot in the given directory, creating the directory if needed
+pub fn next_snapshot_path(directory: &Path) -> Result<PathBuf, SnapshotError> {
+    fs::create_dir_all(directory)?;
+
+    let timestamp: u128 = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_millis()).unwrap_or(0);
+    return Ok(directory.join(format!("snapshot_{}.png", timestamp)));
+}
@@ -0,0 +1,84 @@
+use crate::io::renderer_interface::RendererInterface;
+use crate::io::renderer_utils::{composite_layers, process_particles};
+use crate::io::snapshot::Snapshot;
+use crate::structs::utils::Pixel;
+
+// Renders on the CPU into an in-memory RGBA image, for machines without a GPU
+pub struct SoftwareRenderer {
+    width: usize,
+    height: usize,
+
+    // Layers in BGRA format, laid out like the grid (row 0 is the bottom row)
+    frame_buffer: Vec<u8>,
+    overlay_buffer: Vec<u8>,
+
+    // Composited image in RGBA format, top row first as it appears on screen
+    output_buffer: Vec<u8>,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: usize, height: usize) -> SoftwareRenderer {
+        return SoftwareRenderer {
+            width: width,
+            height: height,
+            frame_buffer: vec![0; width * height * 4],
+            overlay_buffer: vec![0; width * height * 4],
+            output_buffer: vec![0; width * height * 4],
+        };
+    }
+
+    pub fn get_width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn get_height(&self) -> usize {
+        return self.height;
+    }
+
+    // Returns the last composited frame in RGBA format
+    pub fn get_frame(&self) -> &[u8] {
+        return &self.output_buffer;
+    }
+
+    // Returns the RGBA color of a composited pixel, in grid coordinates
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let offset: usize = ((self.height - 1 - y) * self.width + x) * 4;
+        let pixel: &[u8] = &self.output_buffer[offset..offset + 4];
+        return Some([pixel[0], pixel[1], pixel[2], pixel[3]]);
+    }
+}
+
+impl RendererInterface for SoftwareRenderer {
+    fn queue_particles(&mut self, particles_to_queue: &[Pixel]) {
+        process_particles(particles_to_queue, &mut self.frame_buffer);
+    }
+
+    fn queue_debug_overlay_pixels(&mut self, pixels_to_queue: Vec<Pixel>) {
+        for pixel in pixels_to_queue {
+            // Write pixel to overlay buffer in BGRA format
+            let offset = pixel.index * 4;
+            self.overlay_buffer[offset + 0] = pixel.b;
+            self.overlay_buffer[offset + 1] = pixel.g;
+            self.overlay_buffer[offset + 2] = pixel.r;
+            self.overlay_buffer[offset + 3] = pixel.a;
+        }
+    }
+
+    // Nothing draws into the effects layer yet, so only the base and overlay layers are composited
+    fn render_frame(&mut self) {
+        let layers: [&[u8]; 2] = [&self.frame_buffer, &self.overlay_buffer];
+        composite_layers(&layers, self.width, self.height, &mut self.output_buffer);
+    }
+
+    fn capture_frame(&self) -> Option<Snapshot> {
+        return Some(Snapshot {
+            width: self.width,
+            height: self.height,
+            pixels: self.output_buffer.clone(),
+        });
+    }
+}
@@ -1,5 +1,4 @@
 use crate::io::renderer_interface::RendererInterface;
-use crate::structs::particle::Particle;
 use crate::structs::utils::Pixel;
 
 pub struct WebRenderer {
@@ -7,11 +6,11 @@ pub struct WebRenderer {
 }
 
 impl RendererInterface for WebRenderer {
-    fn queue_particles(&mut self, particles_to_queue: &[Particle]) {
+    fn queue_particles(&mut self, _particles_to_queue: &[Pixel]) {
         //
     }
 
-    fn queue_debug_overlay_pixels(&mut self, pixels_to_queue: Vec<Pixel>) {
+    fn queue_debug_overlay_pixels(&mut self, _pixels_to_queue: Vec<Pixel>) {
         //
     }
 
@@ -0,0 +1,305 @@
+use crate::structs::gravity::{Gravity, GravityDirection};
+use crate::structs::grid::Grid;
+use crate::structs::particle::Particle;
+use crate::structs::particle_data::ParticleData;
+use crate::structs::particle_registry::ParticleRegistry;
+use crate::structs::utils::Vector2;
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+// ------------------------ Format ---------------------------------------
+// | Header: magic (4), version (u16), width (u16), height (u16),        |
+// |         registry hash (u64), gravity direction (u8, 0 down,         |
+// |         1 left, 2 up, 3 right), gravity strength (f32)              |
+// | Cells:  runs of identical cells, bottom row first, each run is      |
+// |         length (u32), id (u16), variant (u8), flags (u8),           |
+// |         temperature (f32), age (u32), burn timer (u32),             |
+// |         velocity x (f32), velocity y (f32)                          |
+// | All numbers are little endian                                       |
+// -----------------------------------------------------------------------
+
+pub const WORLD_FILE_MAGIC: [u8; 4] = *b"BOGW";
+pub const WORLD_FILE_VERSION: u16 = 4;
+
+const HEADER_SIZE: usize = 23;
+
+#[derive(Debug)]
+pub enum WorldFileError {
+    Io(std::io::Error),
+    BadMagic,
+    UnsupportedVersion(u16),
+    RegistryMismatch { expected: u64, found: u64 },
+    SizeMismatch { expected: (i32, i32), found: (i32, i32) },
+    UnknownParticleId { id: u16, index: usize },
+    UnknownGravityDirection(u8),
+    InvalidVelocity { index: usize },
+    Truncated,
+    TrailingData,
+}
+
+impl fmt::Display for WorldFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldFileError::Io(error) => write!(f, "could not access world file ({})", error),
+            WorldFileError::BadMagic => write!(f, "not a world file"),
+            WorldFileError::UnsupportedVersion(version) => write!(
+                f,
+                "world file version {} is not supported (expected version {})",
+                version, WORLD_FILE_VERSION
+            ),
+            WorldFileError::RegistryMismatch { expected, found } => write!(
+                f,
+                "world was saved with different particle data (hash {:016x}, current {:016x})",
+                found, expected
+            ),
+            WorldFileError::SizeMismatch { expected, found } => write!(
+                f,
+                "world is {}x{} but the grid is {}x{}",
+                found.0, found.1, expected.0, expected.1
+            ),
+            WorldFileError::UnknownParticleId { id, index } => write!(f, "unknown particle ID {} in cell {}", id, index),
+            WorldFileError::UnknownGravityDirection(code) => write!(f, "unknown gravity direction {}", code),
+            WorldFileError::InvalidVelocity { index } => write!(f, "cell {} has a velocity that isn't a number", index),
+            WorldFileError::Truncated => write!(f, "world file ends unexpectedly"),
+            WorldFileError::TrailingData => write!(f, "world file has more cells than the grid"),
+        }
+    }
+}
+
+impl std::error::Error for WorldFileError {}
+
+impl From<std::io::Error> for WorldFileError {
+    fn from(error: std::io::Error) -> WorldFileError {
+        return WorldFileError::Io(error);
+    }
+}
+
+// --------- Helper Functions ---------
+
+fn encode_gravity_direction(direction: GravityDirection) -> u8 {
+    return match direction {
+        GravityDirection::Down => 0,
+        GravityDirection::Left => 1,
+        GravityDirection::Up => 2,
+        GravityDirection::Right => 3,
+    };
+}
+
+fn decode_gravity_direction(code: u8) -> Option<GravityDirection> {
+    return match code {
+        0 => Some(GravityDirection::Down),
+        1 => Some(GravityDirection::Left),
+        2 => Some(GravityDirection::Up),
+        3 => Some(GravityDirection::Right),
+        _ => None,
+    };
+}
+
+// Everything a cell stores, used to find runs of identical cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CellRecord {
+    id: u16,
+    variant: u8,
+    flags: u8,
+    temperature_bits: u32,
+    age: u32,
+    burn_timer: u32,
+    velocity_bits: (u32, u32),
+}
+
+impl CellRecord {
+    fn from_cell(particle: &Particle, temperature: f32) -> CellRecord {
+        let velocity: Vector2<f32> = particle.get_velocity();
+        return CellRecord {
+            id: particle.id,
+            variant: particle.variant,
+            flags: particle.flags,
+            temperature_bits: temperature.to_bits(),
+            age: particle.age,
+            burn_timer: particle.burn_timer,
+            velocity_bits: (velocity.x.to_bits(), velocity.y.to_bits()),
+        };
+    }
+}
+
+fn write_run(bytes: &mut Vec<u8>, length: u32, record: &CellRecord) {
+    bytes.extend_from_slice(&length.to_le_bytes());
+    bytes.extend_from_slice(&record.id.to_le_bytes());
+    bytes.push(record.variant);
+    bytes.push(record.flags);
+    bytes.extend_from_slice(&record.temperature_bits.to_le_bytes());
+    bytes.extend_from_slice(&record.age.to_le_bytes());
+    bytes.extend_from_slice(&record.burn_timer.to_le_bytes());
+    bytes.extend_from_slice(&record.velocity_bits.0.to_le_bytes());
+    bytes.extend_from_slice(&record.velocity_bits.1.to_le_bytes());
+}
+
+// Reads little endian numbers from a byte slice, failing once it runs out
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], WorldFileError> {
+        let end: usize = self.offset + N;
+        let slice: &[u8] = self.bytes.get(self.offset..end).ok_or(WorldFileError::Truncated)?;
+        self.offset = end;
+
+        let mut array: [u8; N] = [0; N];
+        array.copy_from_slice(slice);
+        return Ok(array);
+    }
+
+    fn read_u8(&mut self) -> Result<u8, WorldFileError> {
+        return Ok(self.take::<1>()?[0]);
+    }
+
+    fn read_u16(&mut self) -> Result<u16, WorldFileError> {
+        return Ok(u16::from_le_bytes(self.take()?));
+    }
+
+    fn read_u32(&mut self) -> Result<u32, WorldFileError> {
+        return Ok(u32::from_le_bytes(self.take()?));
+    }
+
+    fn read_u64(&mut self) -> Result<u64, WorldFileError> {
+        return Ok(u64::from_le_bytes(self.take()?));
+    }
+
+    fn is_empty(&self) -> bool {
+        return self.offset >= self.bytes.len();
+    }
+}
+
+// --------- Encoding ---------
+
+pub fn encode_grid(grid: &Grid) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(HEADER_SIZE);
+
+    // Header
+    bytes.extend_from_slice(&WORLD_FILE_MAGIC);
+    bytes.extend_from_slice(&WORLD_FILE_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(grid.width as u16).to_le_bytes());
+    bytes.extend_from_slice(&(grid.height as u16).to_le_bytes());
+    bytes.extend_from_slice(&grid.get_registry().content_hash().to_le_bytes());
+    let gravity: Gravity = grid.get_gravity();
+    bytes.push(encode_gravity_direction(gravity.direction));
+    bytes.extend_from_slice(&gravity.strength.to_le_bytes());
+
+    // Cells, most of a world is empty space so identical neighbors are stored as one run
+    let mut current_run: Option<(u32, CellRecord)> = None;
+    for (particle, &temperature) in grid.data.iter().zip(grid.temperature.iter()) {
+        let record: CellRecord = CellRecord::from_cell(particle, temperature);
+
+        current_run = match current_run {
+            Some((length, run_record)) if run_record == record => Some((length + 1, run_record)),
+            Some((length, run_record)) => {
+                write_run(&mut bytes, length, &run_record);
+                Some((1, record))
+            }
+            None => Some((1, record)),
+        };
+    }
+    if let Some((length, run_record)) = current_run {
+        write_run(&mut bytes, length, &run_record);
+    }
+
+    return bytes;
+}
+
+pub fn decode_grid(bytes: &[u8], registry: &ParticleRegistry) -> Result<Grid, WorldFileError> {
+    let mut reader: ByteReader = ByteReader { bytes: bytes, offset: 0 };
+
+    // Header
+    if reader.take::<4>().map_err(|_| WorldFileError::BadMagic)? != WORLD_FILE_MAGIC {
+        return Err(WorldFileError::BadMagic);
+    }
+    let version: u16 = reader.read_u16()?;
+    if version != WORLD_FILE_VERSION {
+        return Err(WorldFileError::UnsupportedVersion(version));
+    }
+    let width: u16 = reader.read_u16()?;
+    let height: u16 = reader.read_u16()?;
+    let registry_hash: u64 = reader.read_u64()?;
+    let expected_hash: u64 = registry.content_hash();
+    if registry_hash != expected_hash {
+        return Err(WorldFileError::RegistryMismatch {
+            expected: expected_hash,
+            found: registry_hash,
+        });
+    }
+    let direction_code: u8 = reader.read_u8()?;
+    let direction: GravityDirection = match decode_gravity_direction(direction_code) {
+        Some(val) => val,
+        None => return Err(WorldFileError::UnknownGravityDirection(direction_code)),
+    };
+    let gravity_strength: f32 = f32::from_bits(reader.read_u32()?);
+
+    // Cells, the RNG's state isn't saved so whoever loads the world decides how it carries on
+    let mut grid: Grid = Grid::new(width, height, registry.clone(), 0);
+    let cell_count: usize = width as usize * height as usize;
+    while grid.data.len() < cell_count {
+        let length: u32 = reader.read_u32()?;
+        let id: u16 = reader.read_u16()?;
+        let variant: u8 = reader.read_u8()?;
+        let flags: u8 = reader.read_u8()?;
+        let temperature: f32 = f32::from_bits(reader.read_u32()?);
+        let age: u32 = reader.read_u32()?;
+        let burn_timer: u32 = reader.read_u32()?;
+        let velocity_x: f32 = f32::from_bits(reader.read_u32()?);
+        let velocity_y: f32 = f32::from_bits(reader.read_u32()?);
+
+        let index: usize = grid.data.len();
+        if index + length as usize > cell_count {
+            return Err(WorldFileError::TrailingData);
+        }
+        let particle_data: &ParticleData = match registry.get(id) {
+            Some(val) => val,
+            None => return Err(WorldFileError::UnknownParticleId { id: id, index: index }),
+        };
+        if !velocity_x.is_finite() || !velocity_y.is_finite() {
+            return Err(WorldFileError::InvalidVelocity { index: index });
+        }
+
+        let mut particle: Particle = Particle::with_variant(particle_data, variant);
+        particle.flags = flags;
+        particle.age = age;
+        particle.burn_timer = burn_timer;
+        // A saved velocity may be faster than terminal velocity, setting it clamps it back
+        particle.set_velocity(Vector2::<f32>::new(velocity_x, velocity_y));
+        for _ in 0..length {
+            grid.data.push(particle);
+            grid.temperature.push(temperature);
+        }
+    }
+    if !reader.is_empty() {
+        return Err(WorldFileError::TrailingData);
+    }
+
+    grid.set_gravity(Gravity::new(direction, gravity_strength));
+
+    // Wake everything up so the world carries on where it left off
+    for y in 0..grid.height {
+        for x in 0..grid.width {
+            grid.mark_particle_dirty(x, y, false);
+        }
+    }
+
+    return Ok(grid);
+}
+
+pub fn save_grid(grid: &Grid, path: &Path) -> Result<(), WorldFileError> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    fs::write(path, encode_grid(grid))?;
+    return Ok(());
+}
+
+pub fn load_grid(path: &Path, registry: &ParticleRegistry) -> Result<Grid, WorldFileError> {
+    let bytes: Vec<u8> = fs::read(path)?;
+    return decode_grid(&bytes, registry);
+}
@@ -0,0 +1,5 @@
+pub mod core;
+pub mod io;
+pub mod loader;
+pub mod settings;
+pub mod structs;
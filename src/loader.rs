@@ -1,28 +1,360 @@
+use crate::structs::grid::AMBIENT_TEMPERATURE;
 use crate::structs::particle_data::ParticleData;
+use crate::structs::particle_registry::ParticleRegistry;
+use crate::structs::reaction::Reaction;
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ParticleParseData {
     pub id: Option<u16>,
+    pub file: String,
+    pub line: usize,
+    pub is_override: bool,
     pub name: Option<String>,
     pub category: Option<u16>,
     pub base_color: Option<String>,
     pub variant_color: Option<String>,
     pub is_movable: Option<bool>,
     pub density: Option<f32>,
+    pub dispersion: Option<u16>,
+    pub lifetime: Option<u32>,
+    pub repose_angle: Option<f32>,
+    pub temperature: Option<f32>,
+    pub thermal_conductivity: Option<f32>,
+    pub heat_capacity: Option<f32>,
+    pub melt_point: Option<f32>,
+    pub melt_into: Option<u16>,
+    pub boil_point: Option<f32>,
+    pub boil_into: Option<u16>,
+    pub flammability: Option<f32>,
+    pub burn_time: Option<u32>,
+    pub burn_into: Option<u16>,
 }
 
-pub fn load_particle_data(filepath: &str) -> HashMap<u16, ParticleData> {
+#[derive(Debug, Clone, Default)]
+pub struct ReactionParseData {
+    pub file: String,
+    pub line: usize,
+    pub reactants: Option<(u16, u16)>,
+    pub products: Option<(u16, u16)>,
+    pub probability: Option<f32>,
+}
+
+// --------- Errors ---------
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadErrorReason {
+    FileUnreadable(String),
+    InvalidBlockHeader(String),
+    MalformedLine(String),
+    KeyOutsideBlock(String),
+    UnknownKey(String),
+    MissingKey(&'static str),
+    BadFloat { key: String, value: String },
+    BadInteger { key: String, value: String },
+    BadBool { key: String, value: String },
+    BadIdPair { key: String, value: String },
+    DuplicateId { previous_file: String, previous_line: usize },
+    ReservedIdRange,
+    UnknownParticleId(u16),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadDiagnostic {
+    pub file: String,
+    pub line: usize,
+    pub block_id: Option<u16>,
+    pub reason: LoadErrorReason,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadError {
+    pub diagnostics: Vec<LoadDiagnostic>,
+}
+
+impl fmt::Display for LoadErrorReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            LoadErrorReason::FileUnreadable(error) => write!(f, "could not read file ({})", error),
+            LoadErrorReason::InvalidBlockHeader(header) => write!(f, "invalid block header '{}'", header),
+            LoadErrorReason::MalformedLine(line) => write!(f, "expected 'key: value', found '{}'", line),
+            LoadErrorReason::KeyOutsideBlock(key) => write!(f, "key '{}' is not inside any block", key),
+            LoadErrorReason::UnknownKey(key) => write!(f, "unknown key '{}'", key),
+            LoadErrorReason::MissingKey(key) => write!(f, "missing required key '{}'", key),
+            LoadErrorReason::BadFloat { key, value } => write!(f, "'{}' expects a decimal number, found '{}'", key, value),
+            LoadErrorReason::BadInteger { key, value } => write!(f, "'{}' expects a whole number, found '{}'", key, value),
+            LoadErrorReason::BadBool { key, value } => write!(f, "'{}' expects 'true' or 'false', found '{}'", key, value),
+            LoadErrorReason::BadIdPair { key, value } => write!(f, "'{}' expects two IDs written as 'a + b', found '{}'", key, value),
+            LoadErrorReason::DuplicateId { previous_file, previous_line } => write!(
+                f,
+                "duplicate particle ID (first defined at {}:{}), add 'override: true' to replace it",
+                previous_file, previous_line
+            ),
+            LoadErrorReason::ReservedIdRange => write!(f, "IDs 0 to 9 are reserved for technical particles"),
+            LoadErrorReason::UnknownParticleId(id) => write!(f, "references unknown particle ID {}", id),
+        };
+    }
+}
+
+impl fmt::Display for LoadDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self.block_id {
+            Some(id) => write!(f, "{}:{}: [{}] {}", self.file, self.line, id, self.reason),
+            None => write!(f, "{}:{}: {}", self.file, self.line, self.reason),
+        };
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Failed to load particle data ({} problem(s) found):", self.diagnostics.len())?;
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "  {}", diagnostic)?;
+        }
+        return Ok(());
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+// --------- Conflict Report ---------
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictKind {
+    Overridden { previous_file: String, previous_line: usize },
+    NothingToOverride,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub id: u16,
+    pub file: String,
+    pub line: usize,
+    pub kind: ConflictKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConflictReport {
+    pub conflicts: Vec<Conflict>,
+}
+
+impl ConflictReport {
+    pub fn is_empty(&self) -> bool {
+        return self.conflicts.is_empty();
+    }
+}
+
+impl fmt::Display for ConflictReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Particle data conflicts ({}):", self.conflicts.len())?;
+        for conflict in &self.conflicts {
+            match &conflict.kind {
+                ConflictKind::Overridden { previous_file, previous_line } => writeln!(
+                    f,
+                    "  {}:{}: [{}] overrides block from {}:{}",
+                    conflict.file, conflict.line, conflict.id, previous_file, previous_line
+                )?,
+                ConflictKind::NothingToOverride => writeln!(
+                    f,
+                    "  {}:{}: [{}] is marked as an override but no earlier block has this ID",
+                    conflict.file, conflict.line, conflict.id
+                )?,
+            }
+        }
+        return Ok(());
+    }
+}
+
+// --------- Helper Functions ---------
+
+fn report(diagnostics: &mut Vec<LoadDiagnostic>, file: &str, line: usize, block_id: Option<u16>, reason: LoadErrorReason) {
+    diagnostics.push(LoadDiagnostic {
+        file: file.to_string(),
+        line,
+        block_id,
+        reason,
+    });
+}
+
+fn parse_integer<T: FromStr>(key: &str, value: &str) -> Result<T, LoadErrorReason> {
+    return value.parse::<T>().map_err(|_| LoadErrorReason::BadInteger {
+        key: key.to_string(),
+        value: value.to_string(),
+    });
+}
+
+fn parse_float(key: &str, value: &str) -> Result<f32, LoadErrorReason> {
+    return value.parse::<f32>().map_err(|_| LoadErrorReason::BadFloat {
+        key: key.to_string(),
+        value: value.to_string(),
+    });
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, LoadErrorReason> {
+    return match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(LoadErrorReason::BadBool {
+            key: key.to_string(),
+            value: value.to_string(),
+        }),
+    };
+}
+
+// Parses a pair of particle IDs written as "a + b"
+fn parse_id_pair(key: &str, value: &str) -> Result<(u16, u16), LoadErrorReason> {
+    let pair: Option<(u16, u16)> = value.split_once('+').and_then(|(a_str, b_str)| {
+        let a: u16 = a_str.trim().parse::<u16>().ok()?;
+        let b: u16 = b_str.trim().parse::<u16>().ok()?;
+        return Some((a, b));
+    });
+
+    return pair.ok_or(LoadErrorReason::BadIdPair {
+        key: key.to_string(),
+        value: value.to_string(),
+    });
+}
+
+fn parse_particle_key(data: &mut ParticleParseData, key: &str, value: &str) -> Result<(), LoadErrorReason> {
+    match key {
+        "name" => data.name = Some(value.to_string()),
+        "category" => data.category = Some(parse_integer(key, value)?),
+        "base_color" => data.base_color = Some(value.to_string()),
+        "variant_color" => data.variant_color = Some(value.to_string()),
+        "is_movable" => data.is_movable = Some(parse_bool(key, value)?),
+        "density" => data.density = Some(parse_float(key, value)?),
+        "dispersion" => data.dispersion = Some(parse_integer(key, value)?),
+        "lifetime" => data.lifetime = Some(parse_integer(key, value)?),
+        "repose_angle" => data.repose_angle = Some(parse_float(key, value)?),
+        "temperature" => data.temperature = Some(parse_float(key, value)?),
+        "thermal_conductivity" => data.thermal_conductivity = Some(parse_float(key, value)?),
+        "heat_capacity" => data.heat_capacity = Some(parse_float(key, value)?),
+        "melt_point" => data.melt_point = Some(parse_float(key, value)?),
+        "melt_into" => data.melt_into = Some(parse_integer(key, value)?),
+        "boil_point" => data.boil_point = Some(parse_float(key, value)?),
+        "boil_into" => data.boil_into = Some(parse_integer(key, value)?),
+        "flammability" => data.flammability = Some(parse_float(key, value)?),
+        "burn_time" => data.burn_time = Some(parse_integer(key, value)?),
+        "burn_into" => data.burn_into = Some(parse_integer(key, value)?),
+        "override" => data.is_override = parse_bool(key, value)?,
+        _ => return Err(LoadErrorReason::UnknownKey(key.to_string())),
+    }
+    return Ok(());
+}
+
+fn parse_reaction_key(reaction: &mut ReactionParseData, key: &str, value: &str) -> Result<(), LoadErrorReason> {
+    match key {
+        "reactants" => reaction.reactants = Some(parse_id_pair(key, value)?),
+        "products" => reaction.products = Some(parse_id_pair(key, value)?),
+        "probability" => reaction.probability = Some(parse_float(key, value)?),
+        _ => return Err(LoadErrorReason::UnknownKey(key.to_string())),
+    }
+    return Ok(());
+}
+
+// --------- Loader ---------
+
+#[derive(Debug, Default)]
+struct ParseState {
+    particle_data: HashMap<u16, ParticleParseData>,
+    reaction_data: Vec<ReactionParseData>,
+    conflicts: Vec<Conflict>,
+}
+
+// Loads a single particle data file
+pub fn load_particle_data(filepath: &str) -> Result<ParticleRegistry, LoadError> {
+    let (registry, _) = load_particle_data_sources(&[PathBuf::from(filepath)])?;
+    return Ok(registry);
+}
+
+// Expands directories into the '.data' files they contain, sorted by name so the merge order is predictable
+fn expand_sources(sources: &[PathBuf], diagnostics: &mut Vec<LoadDiagnostic>) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+
+    for source in sources {
+        if !source.is_dir() {
+            files.push(source.clone());
+            continue;
+        }
+
+        match fs::read_dir(source) {
+            Ok(entries) => {
+                let mut data_files: Vec<PathBuf> = entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.is_file() && path.extension().is_some_and(|extension| extension == "data"))
+                    .collect();
+                data_files.sort();
+                files.extend(data_files);
+            }
+            Err(error) => {
+                let file: String = source.display().to_string();
+                report(diagnostics, &file, 0, None, LoadErrorReason::FileUnreadable(error.to_string()));
+            }
+        }
+    }
+
+    return files;
+}
+
+// Merges a finished particle block into the parse state, later blocks only replace earlier ones when marked as overrides
+fn commit_particle_block(data: ParticleParseData, state: &mut ParseState, diagnostics: &mut Vec<LoadDiagnostic>) {
+    let id: u16 = data.id.unwrap_or(0);
+
+    match state.particle_data.get(&id) {
+        Some(previous) if data.is_override => {
+            state.conflicts.push(Conflict {
+                id,
+                file: data.file.clone(),
+                line: data.line,
+                kind: ConflictKind::Overridden {
+                    previous_file: previous.file.clone(),
+                    previous_line: previous.line,
+                },
+            });
+            state.particle_data.insert(id, data);
+        }
+        Some(previous) => {
+            let reason: LoadErrorReason = LoadErrorReason::DuplicateId {
+                previous_file: previous.file.clone(),
+                previous_line: previous.line,
+            };
+            report(diagnostics, &data.file, data.line, Some(id), reason);
+        }
+        None => {
+            if data.is_override {
+                state.conflicts.push(Conflict {
+                    id,
+                    file: data.file.clone(),
+                    line: data.line,
+                    kind: ConflictKind::NothingToOverride,
+                });
+            }
+            state.particle_data.insert(id, data);
+        }
+    }
+}
+
+fn parse_file(filepath: &str, state: &mut ParseState, diagnostics: &mut Vec<LoadDiagnostic>) {
     // Open the particle data file into string
-    let file_text: String = fs::read_to_string(filepath).expect("Could not open particle data file!");
-    let mut particle_data: HashMap<u16, ParticleParseData> = HashMap::new();
+    let file_text: String = match fs::read_to_string(filepath) {
+        Ok(val) => val,
+        Err(error) => {
+            report(diagnostics, filepath, 0, None, LoadErrorReason::FileUnreadable(error.to_string()));
+            return;
+        }
+    };
 
     // Retrieve data
-    let mut current_id: Option<u16> = None;
     let mut current_data: Option<ParticleParseData> = None;
-    let mut processed_ids: Vec<u16> = Vec::new();
-    for lines in file_text.lines() {
+    let mut current_reaction: Option<ReactionParseData> = None;
+    let mut in_ignored_block: bool = false;
+    for (line_index, lines) in file_text.lines().enumerate() {
+        let line_number: usize = line_index + 1;
+
         // Trim line of any whitespaces or tabs
         let this_line: String = lines.trim().to_string();
 
@@ -31,89 +363,133 @@ pub fn load_particle_data(filepath: &str) -> HashMap<u16, ParticleData> {
             continue;
         }
 
-        // We have encountered a new particle block
+        // We have encountered a new particle or reaction block
         if this_line.starts_with('[') && this_line.ends_with(']') {
-            // Wrap up previous particle data
-            if let Some(id) = current_id {
-                if let Some(data) = current_data.take() {
-                    particle_data.insert(id, data);
-                }
+            // Wrap up previous particle, or reaction data
+            if let Some(data) = current_data.take() {
+                commit_particle_block(data, state, diagnostics);
+            }
+            if let Some(reaction) = current_reaction.take() {
+                state.reaction_data.push(reaction);
+            }
+            in_ignored_block = false;
+
+            // Reaction blocks have no id, start a fresh one
+            if this_line == "[reaction]" {
+                current_reaction = Some(ReactionParseData {
+                    file: filepath.to_string(),
+                    line: line_number,
+                    ..Default::default()
+                });
+                continue;
             }
 
             // Retrieve id from this line
             let id_str: &str = &this_line[1..this_line.len() - 1];
-            let id: u16 = id_str.parse::<u16>().unwrap_or(0);
-
-            // Check if id already exists
-            if processed_ids.contains(&id) {
-                current_id = None;
-                current_data = None;
-                println!(
-                    "Particle blocks with duplicate ID found, ignoring particle block with extra ID: {}",
-                    id
-                );
-            } else {
-                // Store a new current id and data if no duplicate ids were found
-                processed_ids.push(id);
-                current_id = Some(id);
-                current_data = Some(ParticleParseData {
-                    id: Some(id),
-                    name: None,
-                    category: None,
-                    base_color: None,
-                    variant_color: None,
-                    is_movable: None,
-                    density: None,
-                });
+            let id: u16 = match id_str.trim().parse::<u16>() {
+                Ok(val) => val,
+                Err(_) => {
+                    report(diagnostics, filepath, line_number, None, LoadErrorReason::InvalidBlockHeader(this_line.clone()));
+                    in_ignored_block = true;
+                    continue;
+                }
+            };
+
+            // IDs 0 to 9 are severed for technical particles
+            if id < 10 {
+                report(diagnostics, filepath, line_number, Some(id), LoadErrorReason::ReservedIdRange);
+                in_ignored_block = true;
+                continue;
             }
+
+            // Duplicates are resolved once the block is complete, as it may be marked as an override
+            current_data = Some(ParticleParseData {
+                id: Some(id),
+                file: filepath.to_string(),
+                line: line_number,
+                ..Default::default()
+            });
             continue;
         }
 
-        // If current data exist, let's populate it's keys
-        if let Some(data) = current_data.as_mut() {
-            if let Some((key_str, val_str)) = this_line.split_once(':') {
-                let value: &str = val_str.trim();
-
-                match key_str {
-                    "name" => data.name = Some(value.to_string()),
-                    "category" => data.category = value.parse::<u16>().ok(),
-                    "base_color" => data.base_color = Some(value.to_string()),
-                    "variant_color" => data.variant_color = Some(value.to_string()),
-                    "is_movable" => {
-                        data.is_movable = match value {
-                            "true" => Some(true),
-                            "false" => Some(false),
-                            _ => None,
-                        }
-                    }
-                    "density" => data.density = value.parse::<f32>().ok(),
-                    _ => (),
-                }
+        // Keys of ignored blocks were already reported through their header
+        if in_ignored_block {
+            continue;
+        }
+
+        let (key_str, val_str) = match this_line.split_once(':') {
+            Some(val) => val,
+            None => {
+                let block_id: Option<u16> = current_data.as_ref().and_then(|data| data.id);
+                report(diagnostics, filepath, line_number, block_id, LoadErrorReason::MalformedLine(this_line.clone()));
+                continue;
             }
+        };
+        let key: &str = key_str.trim();
+        let value: &str = val_str.trim();
+
+        // Populate the current particle's, or reaction's keys
+        let result: Result<(), LoadErrorReason> = if let Some(data) = current_data.as_mut() {
+            parse_particle_key(data, key, value)
+        } else if let Some(reaction) = current_reaction.as_mut() {
+            parse_reaction_key(reaction, key, value)
+        } else {
+            Err(LoadErrorReason::KeyOutsideBlock(key.to_string()))
+        };
+
+        if let Err(reason) = result {
+            let block_id: Option<u16> = current_data.as_ref().and_then(|data| data.id);
+            report(diagnostics, filepath, line_number, block_id, reason);
         }
     }
 
-    // Save the last particle data
-    if let Some(id) = current_id {
-        if let Some(data) = current_data.take() {
-            particle_data.insert(id, data);
-        }
+    // Save the last particle, or reaction data
+    if let Some(data) = current_data.take() {
+        commit_particle_block(data, state, diagnostics);
+    }
+    if let Some(reaction) = current_reaction.take() {
+        state.reaction_data.push(reaction);
     }
+}
+
+// Loads and merges particle data files, and directories of them, in order
+pub fn load_particle_data_sources(sources: &[PathBuf]) -> Result<(ParticleRegistry, ConflictReport), LoadError> {
+    let mut diagnostics: Vec<LoadDiagnostic> = Vec::new();
+    let mut state: ParseState = ParseState::default();
+
+    let load_order: Vec<String> = expand_sources(sources, &mut diagnostics)
+        .iter()
+        .map(|file| file.display().to_string())
+        .collect();
+    for file in &load_order {
+        parse_file(file, &mut state, &mut diagnostics);
+    }
+
+    let ParseState {
+        particle_data,
+        reaction_data,
+        conflicts,
+    } = state;
 
     // Checksum and finalization
     let mut final_particle_data: HashMap<u16, ParticleData> = HashMap::new();
-    for (id, data) in particle_data.into_iter() {
+    let mut sorted_particle_data: Vec<ParticleParseData> = particle_data.into_values().collect();
+    sorted_particle_data.sort_by_key(|data| data.id);
+    let mut transition_targets: Vec<(String, usize, u16, u16)> = Vec::new();
+    for data in sorted_particle_data.into_iter() {
+        let id: u16 = data.id.unwrap_or(0);
+
         if let (Some(name), Some(category), Some(base_color), Some(variant_color), Some(is_movable), Some(density)) = (
-            data.name,
+            data.name.clone(),
             data.category,
-            data.base_color,
-            data.variant_color,
+            data.base_color.clone(),
+            data.variant_color.clone(),
             data.is_movable,
             data.density,
         ) {
-            // Discard any particles with ID ranging from 0 to 9 as they are severed for technical particles
-            if id < 10 {
-                continue;
+            // Particles this one can turn into have to be checked once every particle is known
+            for target_id in [data.melt_into, data.boil_into, data.burn_into].into_iter().flatten() {
+                transition_targets.push((data.file.clone(), data.line, id, target_id));
             }
 
             final_particle_data.insert(
@@ -126,11 +502,38 @@ pub fn load_particle_data(filepath: &str) -> HashMap<u16, ParticleData> {
                     variant_color,
                     is_movable,
                     density,
+                    // Optional keys fall back to inert defaults
+                    dispersion: data.dispersion.unwrap_or(0),
+                    lifetime: data.lifetime.unwrap_or(0),
+                    repose_angle: data.repose_angle.unwrap_or(45.0),
+                    temperature: data.temperature.unwrap_or(AMBIENT_TEMPERATURE),
+                    // Clamp thermal properties to keep heat diffusion numerically stable
+                    thermal_conductivity: data.thermal_conductivity.unwrap_or(0.1).clamp(0.0, 1.0),
+                    heat_capacity: data.heat_capacity.unwrap_or(1.0).max(1.0),
+                    melt_point: data.melt_point,
+                    melt_into: data.melt_into,
+                    boil_point: data.boil_point,
+                    boil_into: data.boil_into,
+                    flammability: data.flammability.unwrap_or(0.0).clamp(0.0, 1.0),
+                    burn_time: data.burn_time.unwrap_or(0),
+                    burn_into: data.burn_into.unwrap_or(0),
                 },
             );
         } else {
-            // ! Todo: warm user about corrupted particle block
-            println!("Corrupted particle block found of ID: {}", id);
+            // Report every required key this block is missing
+            let required_keys: [(&'static str, bool); 6] = [
+                ("name", data.name.is_some()),
+                ("category", data.category.is_some()),
+                ("base_color", data.base_color.is_some()),
+                ("variant_color", data.variant_color.is_some()),
+                ("is_movable", data.is_movable.is_some()),
+                ("density", data.density.is_some()),
+            ];
+            for (key, is_present) in required_keys {
+                if !is_present {
+                    report(&mut diagnostics, &data.file, data.line, Some(id), LoadErrorReason::MissingKey(key));
+                }
+            }
         }
     }
 
@@ -146,8 +549,266 @@ pub fn load_particle_data(filepath: &str) -> HashMap<u16, ParticleData> {
             variant_color: "#0E0E11".to_string(),
             is_movable: true,
             density: 0.0,
+            dispersion: 0,
+            lifetime: 0,
+            repose_angle: 45.0,
+            temperature: AMBIENT_TEMPERATURE,
+            thermal_conductivity: 0.05,
+            heat_capacity: 1.0,
+            melt_point: None,
+            melt_into: None,
+            boil_point: None,
+            boil_into: None,
+            flammability: 0.0,
+            burn_time: 0,
+            burn_into: 0,
         },
     );
 
-    return final_particle_data;
+    // Validate particles referenced by melting, boiling, and burning
+    for (file, line, id, target_id) in transition_targets {
+        if !final_particle_data.contains_key(&target_id) {
+            report(&mut diagnostics, &file, line, Some(id), LoadErrorReason::UnknownParticleId(target_id));
+        }
+    }
+
+    // Validate reactions against the final particle set
+    let mut final_reactions: Vec<Reaction> = Vec::new();
+    for data in reaction_data.into_iter() {
+        if let (Some((reactant_a, reactant_b)), Some((product_a, product_b)), Some(probability)) =
+            (data.reactants, data.products, data.probability)
+        {
+            let mut unknown_ids: Vec<u16> = [reactant_a, reactant_b, product_a, product_b]
+                .into_iter()
+                .filter(|id| !final_particle_data.contains_key(id))
+                .collect();
+            unknown_ids.sort();
+            unknown_ids.dedup();
+            for id in &unknown_ids {
+                report(&mut diagnostics, &data.file, data.line, None, LoadErrorReason::UnknownParticleId(*id));
+            }
+            if !unknown_ids.is_empty() {
+                continue;
+            }
+
+            final_reactions.push(Reaction {
+                reactant_a,
+                reactant_b,
+                product_a,
+                product_b,
+                probability: probability.clamp(0.0, 1.0),
+            });
+        } else {
+            let required_keys: [(&'static str, bool); 3] = [
+                ("reactants", data.reactants.is_some()),
+                ("products", data.products.is_some()),
+                ("probability", data.probability.is_some()),
+            ];
+            for (key, is_present) in required_keys {
+                if !is_present {
+                    report(&mut diagnostics, &data.file, data.line, None, LoadErrorReason::MissingKey(key));
+                }
+            }
+        }
+    }
+
+    // Any problem at all fails the load, so typos never slip through silently
+    if !diagnostics.is_empty() {
+        // Keep the files in the order they were merged, directories that couldn't be read come first
+        diagnostics.sort_by_key(|diagnostic| (load_order.iter().position(|file| *file == diagnostic.file), diagnostic.line));
+        return Err(LoadError { diagnostics });
+    }
+
+    let registry: ParticleRegistry = ParticleRegistry {
+        particles: final_particle_data,
+        reactions: final_reactions,
+    };
+    return Ok((registry, ConflictReport { conflicts }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    // Writes the text into a temporary data file and returns the diagnostics of loading it
+    fn load_diagnostics(file_name: &str, file_text: &str) -> (String, Vec<LoadDiagnostic>) {
+        let file_path: PathBuf = std::env::temp_dir().join(file_name);
+        fs::write(&file_path, file_text).unwrap();
+        let file_path: String = file_path.to_string_lossy().to_string();
+
+        let diagnostics: Vec<LoadDiagnostic> = match load_particle_data(&file_path) {
+            Ok(_) => Vec::new(),
+            Err(error) => error.diagnostics,
+        };
+        return (file_path, diagnostics);
+    }
+
+    fn diagnostic(file: &str, line: usize, block_id: Option<u16>, reason: LoadErrorReason) -> LoadDiagnostic {
+        return LoadDiagnostic {
+            file: file.to_string(),
+            line,
+            block_id,
+            reason,
+        };
+    }
+
+    #[test]
+    fn valid_file_loads_without_diagnostics() {
+        let file_text: &str = "[10]\nname: Stone\ncategory: 1\nbase_color: #7C7872\nvariant_color: #5E5A55\nis_movable: false\ndensity: 55.0\n";
+        let (_, diagnostics) = load_diagnostics("bog_loader_valid.data", file_text);
+        assert_eq!(diagnostics, Vec::new());
+    }
+
+    #[test]
+    fn missing_keys_are_reported_on_the_block_header() {
+        let file_text: &str = "# Stone\n[10]\nname: Stone\ncategory: 1\nbase_color: #7C7872\nis_movable: false\n";
+        let (file, diagnostics) = load_diagnostics("bog_loader_missing_key.data", file_text);
+        assert_eq!(
+            diagnostics,
+            vec![
+                diagnostic(&file, 2, Some(10), LoadErrorReason::MissingKey("variant_color")),
+                diagnostic(&file, 2, Some(10), LoadErrorReason::MissingKey("density")),
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_floats_are_reported_on_their_line() {
+        let file_text: &str = "[10]\nname: Stone\ncategory: 1\nbase_color: #7C7872\nvariant_color: #5E5A55\nis_movable: false\ndensity: 55.0\nthermal_conductivity: high\n";
+        let (file, diagnostics) = load_diagnostics("bog_loader_bad_float.data", file_text);
+        let reason: LoadErrorReason = LoadErrorReason::BadFloat {
+            key: "thermal_conductivity".to_string(),
+            value: "high".to_string(),
+        };
+        assert_eq!(diagnostics, vec![diagnostic(&file, 8, Some(10), reason)]);
+    }
+
+    #[test]
+    fn duplicate_ids_are_reported_and_their_block_ignored() {
+        let file_text: &str = "[10]\nname: Stone\ncategory: 1\nbase_color: #7C7872\nvariant_color: #5E5A55\nis_movable: false\ndensity: 55.0\n\n[10]\nname: Copy\n";
+        let (file, diagnostics) = load_diagnostics("bog_loader_duplicate_id.data", file_text);
+        let reason: LoadErrorReason = LoadErrorReason::DuplicateId {
+            previous_file: file.clone(),
+            previous_line: 1,
+        };
+        assert_eq!(diagnostics, vec![diagnostic(&file, 9, Some(10), reason)]);
+    }
+
+    #[test]
+    fn unknown_keys_are_reported_on_their_line() {
+        let file_text: &str = "[10]\nname: Stone\ncategory: 1\nbase_color: #7C7872\nvariant_color: #5E5A55\nhardness: 3\nis_movable: false\ndensity: 55.0\n";
+        let (file, diagnostics) = load_diagnostics("bog_loader_unknown_key.data", file_text);
+        let reason: LoadErrorReason = LoadErrorReason::UnknownKey("hardness".to_string());
+        assert_eq!(diagnostics, vec![diagnostic(&file, 6, Some(10), reason)]);
+    }
+
+    #[test]
+    fn reserved_ids_are_reported_and_their_block_ignored() {
+        let file_text: &str = "\n[5]\nname: Technical\ncategory: 0\n";
+        let (file, diagnostics) = load_diagnostics("bog_loader_reserved_id.data", file_text);
+        assert_eq!(diagnostics, vec![diagnostic(&file, 2, Some(5), LoadErrorReason::ReservedIdRange)]);
+    }
+
+    // Creates a fresh temporary directory holding the given data files
+    fn create_data_directory(directory_name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let directory: PathBuf = std::env::temp_dir().join(directory_name);
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        for (file_name, file_text) in files {
+            fs::write(directory.join(file_name), file_text).unwrap();
+        }
+        return directory;
+    }
+
+    fn particle_block(id: u16, name: &str, extra_keys: &str) -> String {
+        return format!(
+            "[{}]\nname: {}\ncategory: 1\nbase_color: #7C7872\nvariant_color: #5E5A55\nis_movable: false\ndensity: 55.0\n{}",
+            id, name, extra_keys
+        );
+    }
+
+    #[test]
+    fn directories_merge_their_data_files_in_name_order() {
+        let base_text: String = particle_block(10, "Stone", "") + &particle_block(11, "Wall", "");
+        let mod_text: String = particle_block(10, "Marble", "override: true\n") + &particle_block(500, "Crystal", "");
+        let directory: PathBuf = create_data_directory(
+            "bog_loader_merge_order",
+            &[("b_mod.data", &mod_text), ("a_base.data", &base_text), ("notes.txt", "not particle data")],
+        );
+
+        let (registry, conflicts) = load_particle_data_sources(std::slice::from_ref(&directory)).unwrap();
+
+        // The mod is merged after the base, so its override wins, and other files in the directory are skipped
+        assert_eq!(registry.get(10).unwrap().name, "Marble");
+        assert_eq!(registry.get(11).unwrap().name, "Wall");
+        assert_eq!(registry.get(500).unwrap().name, "Crystal");
+        assert_eq!(registry.particles.len(), 4);
+
+        let overridden: Conflict = Conflict {
+            id: 10,
+            file: directory.join("b_mod.data").display().to_string(),
+            line: 1,
+            kind: ConflictKind::Overridden {
+                previous_file: directory.join("a_base.data").display().to_string(),
+                previous_line: 1,
+            },
+        };
+        assert_eq!(conflicts.conflicts, vec![overridden]);
+    }
+
+    #[test]
+    fn overrides_without_an_earlier_block_are_reported() {
+        let directory: PathBuf = create_data_directory(
+            "bog_loader_nothing_to_override",
+            &[("a_base.data", &particle_block(10, "Stone", "")), ("b_mod.data", &particle_block(12, "Wood", "override: true\n"))],
+        );
+
+        let (registry, conflicts) = load_particle_data_sources(std::slice::from_ref(&directory)).unwrap();
+        assert_eq!(registry.get(12).unwrap().name, "Wood");
+
+        let nothing_to_override: Conflict = Conflict {
+            id: 12,
+            file: directory.join("b_mod.data").display().to_string(),
+            line: 1,
+            kind: ConflictKind::NothingToOverride,
+        };
+        assert_eq!(conflicts.conflicts, vec![nothing_to_override]);
+    }
+
+    #[test]
+    fn duplicates_across_files_need_an_override() {
+        let directory: PathBuf = create_data_directory(
+            "bog_loader_duplicate_across_files",
+            &[("a_base.data", &particle_block(10, "Stone", "")), ("b_mod.data", &particle_block(10, "Marble", ""))],
+        );
+
+        let diagnostics: Vec<LoadDiagnostic> = load_particle_data_sources(std::slice::from_ref(&directory)).unwrap_err().diagnostics;
+        let reason: LoadErrorReason = LoadErrorReason::DuplicateId {
+            previous_file: directory.join("a_base.data").display().to_string(),
+            previous_line: 1,
+        };
+        assert_eq!(diagnostics, vec![diagnostic(&directory.join("b_mod.data").display().to_string(), 1, Some(10), reason)]);
+    }
+
+    #[test]
+    fn diagnostics_keep_the_order_files_were_loaded_in() {
+        let directory: PathBuf = create_data_directory(
+            "bog_loader_diagnostic_order",
+            &[("z_first.data", "[10]\nname: Stone\n"), ("a_second.data", "\n[11]\nhardness: 3\n")],
+        );
+        let first_file: PathBuf = directory.join("z_first.data");
+        let second_file: PathBuf = directory.join("a_second.data");
+
+        let diagnostics: Vec<LoadDiagnostic> = load_particle_data_sources(&[first_file.clone(), second_file.clone()]).unwrap_err().diagnostics;
+        let files_and_lines: Vec<(String, usize)> = diagnostics.iter().map(|diagnostic| (diagnostic.file.clone(), diagnostic.line)).collect();
+
+        // Missing keys are only found after every file was parsed, but still end up with their file
+        let first_file: String = first_file.display().to_string();
+        let second_file: String = second_file.display().to_string();
+        let mut expected: Vec<(String, usize)> = vec![(first_file, 1); 5];
+        expected.extend(vec![(second_file.clone(), 2); 6]);
+        expected.push((second_file, 3));
+        assert_eq!(files_and_lines, expected);
+    }
 }
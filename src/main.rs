@@ -1,13 +1,8 @@
-pub mod core;
-pub mod io;
-pub mod loader;
-pub mod settings;
-pub mod structs;
-
-use core::app_manager_interface::AppManagerInterface;
-use core::native_app_manager::NativeAppManager;
-use core::web_app_manager::WebAppManager;
-use settings::Settings;
+use bog_engine::core::app_manager_interface::AppManagerInterface;
+#[cfg(not(target_arch = "wasm32"))]
+use bog_engine::core::native_app_manager::NativeAppManager;
+#[cfg(target_arch = "wasm32")]
+use bog_engine::core::web_app_manager::WebAppManager;
 
 fn main() {
     #[cfg(target_arch = "wasm32")]
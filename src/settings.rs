@@ -1,9 +1,34 @@
+use crate::io::image_import::FitMode;
+use crate::structs::gravity::Gravity;
+
+use std::env;
+use std::path::{Path, PathBuf};
+
 pub struct Settings {
     pub game_width: usize,
     pub game_height: usize,
     pub debug_enabled: bool,
+    // Milliseconds between drawn frames and between physics ticks
     pub render_update_interval: u64,
     pub physics_update_interval: u64,
+    // Ticks a single frame may run to catch up at normal speed, anything beyond that is dropped
+    pub max_physics_ticks_per_frame: u32,
+    // Simulation speed, below one is slow motion and above one is fast forward
+    pub time_scale: f64,
+    // Gravity new worlds start out with, it can be changed while the simulation runs
+    pub gravity: Gravity,
+    // Particle data files, or directories of '.data' files, loaded in order
+    pub particle_data_paths: Vec<PathBuf>,
+    pub snapshot_directory: PathBuf,
+    pub world_save_path: PathBuf,
+    // Levels imported from images, an optional legend maps colors to particle IDs
+    pub level_fit_mode: FitMode,
+    pub level_legend_path: Option<PathBuf>,
+    // Memory the undo history may take up, in bytes
+    pub undo_memory_budget: usize,
+    pub key_bindings_path: PathBuf,
+    // Seed for every random decision in the simulation, a random one is picked when unset
+    pub seed: Option<u64>,
 }
 
 impl Default for Settings {
@@ -14,6 +39,63 @@ impl Default for Settings {
             debug_enabled: true,
             render_update_interval: 15,
             physics_update_interval: 15,
+            max_physics_ticks_per_frame: 4,
+            time_scale: 1.0,
+            gravity: Gravity::default(),
+            particle_data_paths: vec![find_data_directory().join("particles.data")],
+            snapshot_directory: PathBuf::from("snapshots"),
+            world_save_path: PathBuf::from("saves/world.bog"),
+            level_fit_mode: FitMode::Scale,
+            level_legend_path: None,
+            undo_memory_budget: 16 * 1024 * 1024,
+            key_bindings_path: find_data_directory().join("key_bindings.data"),
+            seed: None,
         };
     }
 }
+
+impl Settings {
+    // Applies command line arguments, '--particle-data <path>' loads another file or directory after the built-in data
+    pub fn apply_args(&mut self, args: &[String]) -> Result<(), String> {
+        let mut arg_iter = args.iter();
+        while let Some(arg) = arg_iter.next() {
+            match arg.as_str() {
+                "--particle-data" => match arg_iter.next() {
+                    Some(path) => self.particle_data_paths.push(PathBuf::from(path)),
+                    None => return Err(format!("'{}' expects a file or directory", arg)),
+                },
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            }
+        }
+        return Ok(());
+    }
+}
+
+// Built-in data ships in a 'data' directory next to the executable, without one the data is read from the
+// source tree the binary was built from, wherever it's started from
+pub fn find_data_directory() -> PathBuf {
+    let shipped_directory: Option<PathBuf> = env::current_exe()
+        .ok()
+        .and_then(|executable| executable.parent().map(|directory| directory.join("data")));
+
+    return match shipped_directory {
+        Some(directory) if directory.is_dir() => directory,
+        _ => Path::new(env!("CARGO_MANIFEST_DIR")).join("src/data"),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn particle_data_arguments_load_after_the_built_in_data() {
+        let mut settings: Settings = Settings::default();
+        let args: Vec<String> = vec!["--particle-data".to_string(), "mods/lava_pack".to_string()];
+        settings.apply_args(&args).unwrap();
+
+        assert_eq!(settings.particle_data_paths, vec![find_data_directory().join("particles.data"), PathBuf::from("mods/lava_pack")]);
+        assert!(settings.apply_args(&["--particle-data".to_string()]).is_err());
+        assert!(settings.apply_args(&["--fullscreen".to_string()]).is_err());
+    }
+}
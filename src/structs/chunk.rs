@@ -0,0 +1,103 @@
+use std::sync::atomic::{AtomicI32, Ordering};
+
+// Width and height of a chunk in cells, the same blocks the native renderer tracks its dirty regions in
+pub const CHUNK_SIZE: i32 = 32;
+
+// Furthest from its own chunk a particle update may read or write a cell
+// Chunks updated in the same checkerboard pass are a whole chunk apart, so as long as every update stays
+// within half a chunk of its own, two of them can never touch the same cell
+pub const MAX_PARTICLE_REACH: i32 = CHUNK_SIZE / 2;
+
+// Rectangle of cells in grid coordinates, both corners inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellBounds {
+    pub min_x: i32,
+    pub min_y: i32,
+    pub max_x: i32,
+    pub max_y: i32,
+}
+
+impl CellBounds {
+    pub fn new(x: i32, y: i32) -> CellBounds {
+        return CellBounds {
+            min_x: x,
+            min_y: y,
+            max_x: x,
+            max_y: y,
+        };
+    }
+
+    // Grows the bounds to cover the given cell
+    pub fn include(&mut self, x: i32, y: i32) {
+        self.min_x = self.min_x.min(x);
+        self.min_y = self.min_y.min(y);
+        self.max_x = self.max_x.max(x);
+        self.max_y = self.max_y.max(y);
+    }
+}
+
+// A square block of the grid, it sleeps and costs nothing per tick while nothing in it changes
+#[derive(Debug)]
+pub struct Chunk {
+    // Bounds of the cells waiting to be updated, empty while min is past max
+    // Neighboring chunks updating on other threads can mark cells here, so the bounds only ever grow atomically
+    dirty_min_x: AtomicI32,
+    dirty_min_y: AtomicI32,
+    dirty_max_x: AtomicI32,
+    dirty_max_y: AtomicI32,
+
+    // Heat is still flowing through the chunk, so it has to be conducted even when nothing moves
+    pub is_heat_active: bool,
+}
+
+impl Default for Chunk {
+    fn default() -> Chunk {
+        return Chunk {
+            dirty_min_x: AtomicI32::new(i32::MAX),
+            dirty_min_y: AtomicI32::new(i32::MAX),
+            dirty_max_x: AtomicI32::new(i32::MIN),
+            dirty_max_y: AtomicI32::new(i32::MIN),
+
+            is_heat_active: false,
+        };
+    }
+}
+
+impl Chunk {
+    pub fn is_awake(&self) -> bool {
+        return self.get_dirty_bounds().is_some() || self.is_heat_active;
+    }
+
+    pub fn get_dirty_bounds(&self) -> Option<CellBounds> {
+        let bounds: CellBounds = CellBounds {
+            min_x: self.dirty_min_x.load(Ordering::Relaxed),
+            min_y: self.dirty_min_y.load(Ordering::Relaxed),
+            max_x: self.dirty_max_x.load(Ordering::Relaxed),
+            max_y: self.dirty_max_y.load(Ordering::Relaxed),
+        };
+
+        if bounds.min_x > bounds.max_x {
+            return None;
+        }
+        return Some(bounds);
+    }
+
+    pub fn mark_dirty(&self, x: i32, y: i32) {
+        self.dirty_min_x.fetch_min(x, Ordering::Relaxed);
+        self.dirty_min_y.fetch_min(y, Ordering::Relaxed);
+        self.dirty_max_x.fetch_max(x, Ordering::Relaxed);
+        self.dirty_max_y.fetch_max(y, Ordering::Relaxed);
+    }
+
+    // Empties the dirty bounds, returning what they were
+    pub fn take_dirty_bounds(&mut self) -> Option<CellBounds> {
+        let bounds: Option<CellBounds> = self.get_dirty_bounds();
+
+        *self.dirty_min_x.get_mut() = i32::MAX;
+        *self.dirty_min_y.get_mut() = i32::MAX;
+        *self.dirty_max_x.get_mut() = i32::MIN;
+        *self.dirty_max_y.get_mut() = i32::MIN;
+
+        return bounds;
+    }
+}
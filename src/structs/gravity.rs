@@ -0,0 +1,122 @@
+use crate::structs::utils::{Offset2, Vector2};
+
+use std::fmt;
+
+// Strongest pull gravity can be set to, as a multiple of normal gravity
+pub const MAX_GRAVITY_STRENGTH: f32 = 4.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GravityDirection {
+    Down,
+    Left,
+    Up,
+    Right,
+}
+
+impl GravityDirection {
+    pub fn from_name(name: &str) -> Option<GravityDirection> {
+        return match name {
+            "down" => Some(GravityDirection::Down),
+            "left" => Some(GravityDirection::Left),
+            "up" => Some(GravityDirection::Up),
+            "right" => Some(GravityDirection::Right),
+            _ => None,
+        };
+    }
+
+    pub fn name(&self) -> &'static str {
+        return match self {
+            GravityDirection::Down => "down",
+            GravityDirection::Left => "left",
+            GravityDirection::Up => "up",
+            GravityDirection::Right => "right",
+        };
+    }
+
+    // A quarter turn clockwise on screen, down turns to left
+    pub fn rotate_clockwise(&self) -> GravityDirection {
+        return match self {
+            GravityDirection::Down => GravityDirection::Left,
+            GravityDirection::Left => GravityDirection::Up,
+            GravityDirection::Up => GravityDirection::Right,
+            GravityDirection::Right => GravityDirection::Down,
+        };
+    }
+}
+
+// Which way particles fall and how hard, a strength of zero leaves everything floating
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gravity {
+    pub direction: GravityDirection,
+    pub strength: f32,
+}
+
+impl Default for Gravity {
+    fn default() -> Gravity {
+        return Gravity {
+            direction: GravityDirection::Down,
+            strength: 1.0,
+        };
+    }
+}
+
+impl fmt::Display for Gravity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "Zero gravity");
+        }
+        return write!(f, "Gravity {} at {}x", self.direction.name(), self.strength);
+    }
+}
+
+impl Gravity {
+    pub fn new(direction: GravityDirection, strength: f32) -> Gravity {
+        return Gravity {
+            direction: direction,
+            strength: if strength.is_finite() { strength.clamp(0.0, MAX_GRAVITY_STRENGTH) } else { 0.0 },
+        };
+    }
+
+    pub fn is_zero(&self) -> bool {
+        return self.strength <= 0.0;
+    }
+
+    // Movement handlers are written as if gravity pulled down (dy of -1), this turns their offsets the way it really pulls
+    pub fn orient(&self, offset: Offset2<i32>) -> Offset2<i32> {
+        let (dx, dy) = (offset.dx, offset.dy);
+        return match self.direction {
+            GravityDirection::Down => Offset2::<i32>::new(dx, dy),
+            GravityDirection::Left => Offset2::<i32>::new(dy, -dx),
+            GravityDirection::Up => Offset2::<i32>::new(-dx, -dy),
+            GravityDirection::Right => Offset2::<i32>::new(-dy, dx),
+        };
+    }
+
+    // Same as orient, for velocities
+    pub fn orient_velocity(&self, velocity: Vector2<f32>) -> Vector2<f32> {
+        let (x, y) = (velocity.x, velocity.y);
+        return match self.direction {
+            GravityDirection::Down => Vector2::<f32>::new(x, y),
+            GravityDirection::Left => Vector2::<f32>::new(y, -x),
+            GravityDirection::Up => Vector2::<f32>::new(-x, -y),
+            GravityDirection::Right => Vector2::<f32>::new(-y, x),
+        };
+    }
+
+    // Undoes orient_velocity, so a particle's velocity can be read as if gravity pulled down
+    pub fn unorient_velocity(&self, velocity: Vector2<f32>) -> Vector2<f32> {
+        let (x, y) = (velocity.x, velocity.y);
+        return match self.direction {
+            GravityDirection::Down => Vector2::<f32>::new(x, y),
+            GravityDirection::Left => Vector2::<f32>::new(-y, x),
+            GravityDirection::Up => Vector2::<f32>::new(-x, -y),
+            GravityDirection::Right => Vector2::<f32>::new(y, -x),
+        };
+    }
+
+    // How far up a cell is, cells further down settle first
+    pub fn get_height(&self, x: i32, y: i32) -> i32 {
+        let down: Offset2<i32> = self.orient(Offset2::<i32>::new(0, -1));
+        return -(x * down.dx + y * down.dy);
+    }
+}
@@ -1,39 +1,80 @@
-use crate::loader::load_particle_data;
-use crate::structs::particle::Particle;
+use crate::structs::chunk::{CHUNK_SIZE, CellBounds, Chunk};
+use crate::structs::color::Color;
+use crate::structs::gravity::Gravity;
+use crate::structs::grid_cells::GridCells;
+use crate::structs::particle::{PARTICLE_FLAG_SCORCHED, Particle};
 use crate::structs::particle_data::ParticleData;
-use crate::structs::utils::{Offset2, Vector2};
-use std::collections::HashMap;
+use crate::structs::particle_properties::ParticleProperties;
+use crate::structs::particle_registry::ParticleRegistry;
+use crate::structs::utils::Pixel;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rayon::prelude::*;
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use rand::{Rng, prelude::SliceRandom, prelude::ThreadRng, random};
+pub const AMBIENT_TEMPERATURE: f32 = 20.0;
 
-const MOORE_NEIGHBORS: [(i32, i32); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
-const VON_NEUMANN_NEIGHBORS: [(i32, i32); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+// Chunks whose temperatures change less than this per tick stop conducting heat until something wakes them
+const HEAT_SETTLE_THRESHOLD: f32 = 0.01;
+
+pub const MOORE_NEIGHBORS: [(i32, i32); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+pub const VON_NEUMANN_NEIGHBORS: [(i32, i32); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
 
 pub struct Grid {
     pub width: i32,
     pub height: i32,
+
+    // A compact record per cell, and its temperature alongside
     pub data: Vec<Particle>,
-    pub dirty_particles: HashSet<u32>,
+    pub temperature: Vec<f32>,
+
+    // Cells waiting to be updated, the chunks they fall in keep track of where they are
+    dirty_cells: Vec<AtomicBool>,
+    chunks: Vec<Chunk>,
+    chunk_count_x: i32,
+    chunk_count_y: i32,
+
+    // Every random decision made on the grid comes from here, so a seed reproduces a whole run
+    pub rng: StdRng,
 
-    particle_data_map: HashMap<u16, ParticleData>,
+    // Which way everything falls, part of the world so it's saved along with it
+    gravity: Gravity,
+
+    registry: ParticleRegistry,
+    properties: Vec<ParticleProperties>,
 }
 
 impl Grid {
-    pub fn new(width: u16, height: u16) -> Grid {
-        // Load particle data for this grid
-        let particle_data_map: HashMap<u16, ParticleData> = load_particle_data("./src/data/particles.data");
+    pub fn new(width: u16, height: u16, registry: ParticleRegistry, seed: u64) -> Grid {
+        // Flatten what the simulation reads every tick into a table indexed by particle id for fast lookups
+        let properties: Vec<ParticleProperties> = ParticleProperties::build_table(&registry);
 
         // Create a new empty data for this grid
         let data: Vec<Particle> = Vec::with_capacity(width as usize * height as usize);
+        let temperature: Vec<f32> = Vec::with_capacity(width as usize * height as usize);
+
+        // Split the grid into chunks, the last row and column of chunks may be cut short
+        let chunk_count_x: i32 = (width as i32 + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        let chunk_count_y: i32 = (height as i32 + CHUNK_SIZE - 1) / CHUNK_SIZE;
 
         return Grid {
             width: width as i32,
             height: height as i32,
             data: data,
-            dirty_particles: HashSet::<u32>::new(),
+            temperature: temperature,
+
+            dirty_cells: (0..width as usize * height as usize).map(|_| AtomicBool::new(false)).collect(),
+            chunks: (0..chunk_count_x * chunk_count_y).map(|_| Chunk::default()).collect(),
+            chunk_count_x: chunk_count_x,
+            chunk_count_y: chunk_count_y,
+
+            rng: StdRng::seed_from_u64(seed),
 
-            particle_data_map: particle_data_map,
+            gravity: Gravity::default(),
+
+            registry: registry,
+            properties: properties,
         };
     }
 
@@ -42,72 +83,153 @@ impl Grid {
         return x >= 0 && x < self.width && y >= 0 && y < self.height;
     }
 
+    pub fn get_particle_data(&self, particle_id: u16) -> Option<&ParticleData> {
+        return self.registry.get(particle_id);
+    }
+
+    pub fn get_registry(&self) -> &ParticleRegistry {
+        return &self.registry;
+    }
+
+    pub fn get_properties(&self, particle_id: u16) -> &ParticleProperties {
+        return &self.properties[(particle_id as usize).min(self.properties.len() - 1)];
+    }
+
+    pub fn get_gravity(&self) -> Gravity {
+        return self.gravity;
+    }
+
+    // Wakes every cell, particles resting against the old gravity may have to move under the new one
+    pub fn set_gravity(&mut self, gravity: Gravity) {
+        if gravity == self.gravity {
+            return;
+        }
+        self.gravity = gravity;
+
+        let (mut cells, _) = self.cells();
+        for y in 0..cells.height {
+            for x in 0..cells.width {
+                cells.mark_particle_dirty(x, y, false);
+            }
+        }
+    }
+
     pub fn populate(&mut self, particle_id: u16) {
         // Retrive particle from particle data map using the provided id
-        let particle_data: &ParticleData = self.particle_data_map.get(&particle_id).unwrap();
+        let particle_data: &ParticleData = self.registry.get(particle_id).unwrap();
 
         // Create grid data and populate it with the provided particles
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let index: u32 = (y * self.width + x) as u32;
-                let handle: u32 = index;
-
-                let mut particle: Particle = Particle::new(particle_data, x, y, handle);
-                particle.index = index;
-                self.data.push(particle);
-            }
+        for _ in 0..self.width * self.height {
+            self.data.push(Particle::new(particle_data, &mut self.rng));
+            self.temperature.push(particle_data.temperature);
         }
     }
 
+    // A handle onto the cells for the physics, along with the RNG they draw from
+    pub fn cells(&mut self) -> (GridCells<'_>, &mut StdRng) {
+        let cells: GridCells = GridCells::new(
+            self.width,
+            self.height,
+            &mut self.data,
+            &mut self.temperature,
+            &self.dirty_cells,
+            &self.chunks,
+            self.chunk_count_x,
+            &self.properties,
+            &self.registry,
+        );
+        return (cells, &mut self.rng);
+    }
+
     pub fn mark_particle_dirty(&mut self, x: i32, y: i32, mark_neighbors_dirty: bool) {
-        // Get particle's index
-        let particle_index: u32 = (y * self.width + x) as u32;
+        self.cells().0.mark_particle_dirty(x, y, mark_neighbors_dirty);
+    }
 
-        // Add the particle's index to mark it dirty
-        self.dirty_particles.insert(particle_index);
+    pub fn is_particle_dirty(&self, x: i32, y: i32) -> bool {
+        return self.is_in_bounds(x, y) && self.dirty_cells[(y * self.width + x) as usize].load(Ordering::Relaxed);
+    }
 
-        // Mark particle's neighbors dirty
-        if mark_neighbors_dirty {
-            // Get particle's neighbors
-            let neighbor_indices: Vec<u32> = self.get_neighbor_indices_of(x, y, &MOORE_NEIGHBORS);
+    pub fn has_dirty_particles(&self) -> bool {
+        return self.chunks.iter().any(|chunk| chunk.get_dirty_bounds().is_some());
+    }
 
-            // Mark them dirty
-            for neighbor_index in neighbor_indices {
-                self.dirty_particles.insert(neighbor_index);
+    // Returns the dirty cells, only looking inside the dirty bounds of each chunk
+    pub fn get_dirty_indices(&self) -> Vec<u32> {
+        let mut indices: Vec<u32> = Vec::new();
+
+        for chunk in &self.chunks {
+            if let Some(bounds) = chunk.get_dirty_bounds() {
+                for y in bounds.min_y..=bounds.max_y {
+                    for x in bounds.min_x..=bounds.max_x {
+                        let index: u32 = (y * self.width + x) as u32;
+                        if self.dirty_cells[index as usize].load(Ordering::Relaxed) {
+                            indices.push(index);
+                        }
+                    }
+                }
             }
         }
+
+        return indices;
     }
 
-    // --------- Methods ---------
+    // Returns the dirty cells and clears them, cells marked from here on are left for the next tick
+    pub fn take_dirty_indices(&mut self) -> Vec<u32> {
+        let indices: Vec<u32> = self.get_dirty_indices();
 
-    pub fn create_particle_at(&mut self, x: i32, y: i32, particle_id: u16, mark_dirty: bool, mark_neighbors_dirty: bool) -> bool {
-        // x and y axis are out of bounds, return
-        if !(self.is_in_bounds(x, y)) {
-            return false;
+        for &index in &indices {
+            *self.dirty_cells[index as usize].get_mut() = false;
+        }
+        // Whatever changed in a chunk may have moved heat around, so it keeps conducting until it evens out
+        for chunk in &mut self.chunks {
+            if chunk.take_dirty_bounds().is_some() {
+                chunk.is_heat_active = true;
+            }
         }
 
-        // Retrieve particle data
-        let particle_data: &ParticleData = match self.particle_data_map.get(&particle_id) {
-            Some(val) => val,
-            None => return false, // Cannot create particle, invalid particle id, return
-        };
+        return indices;
+    }
 
-        // Instanciate a new particle
-        let mut new_particle: Particle = Particle::new(particle_data, x, y, 1);
+    // --------- Chunks ---------
 
-        // Set the new particle's index
-        let index: u32 = (y * self.width + x) as u32;
-        new_particle.index = index;
+    pub fn get_chunk_count_x(&self) -> i32 {
+        return self.chunk_count_x;
+    }
 
-        // Assigning the new particle to the data
-        self.data[index as usize] = new_particle;
+    pub fn get_chunk_count_y(&self) -> i32 {
+        return self.chunk_count_y;
+    }
 
-        // Handle dirty logic
-        if mark_dirty {
-            self.mark_particle_dirty(x, y, mark_neighbors_dirty);
-        }
+    pub fn get_chunk_index_at(&self, x: i32, y: i32) -> usize {
+        return ((y / CHUNK_SIZE) * self.chunk_count_x + x / CHUNK_SIZE) as usize;
+    }
 
-        return true;
+    pub fn get_chunk(&self, chunk_index: usize) -> Option<&Chunk> {
+        return self.chunks.get(chunk_index);
+    }
+
+    pub fn get_awake_chunk_indices(&self) -> Vec<usize> {
+        return (0..self.chunks.len()).filter(|&chunk_index| self.chunks[chunk_index].is_awake()).collect();
+    }
+
+    // Cells covered by a chunk, cut off at the edges of the grid
+    pub fn get_chunk_bounds(&self, chunk_index: usize) -> CellBounds {
+        let chunk_x: i32 = chunk_index as i32 % self.chunk_count_x;
+        let chunk_y: i32 = chunk_index as i32 / self.chunk_count_x;
+
+        return CellBounds {
+            min_x: chunk_x * CHUNK_SIZE,
+            min_y: chunk_y * CHUNK_SIZE,
+            max_x: ((chunk_x + 1) * CHUNK_SIZE).min(self.width) - 1,
+            max_y: ((chunk_y + 1) * CHUNK_SIZE).min(self.height) - 1,
+        };
+    }
+
+    // --------- Methods ---------
+
+    pub fn create_particle_at(&mut self, x: i32, y: i32, particle_id: u16, mark_dirty: bool, mark_neighbors_dirty: bool) -> bool {
+        let (mut cells, rng) = self.cells();
+        return cells.create_particle_at(rng, x, y, particle_id, mark_dirty, mark_neighbors_dirty);
     }
 
     pub fn get_particle_at<'a>(&'a self, x: i32, y: i32) -> Option<&'a Particle> {
@@ -121,111 +243,223 @@ impl Grid {
     }
 
     pub fn get_neighbor_indices_of(&self, x: i32, y: i32, offsets: &[(i32, i32)]) -> Vec<u32> {
-        let mut neighbors: Vec<u32> = Vec::with_capacity(offsets.len());
+        return get_neighbor_indices(self.width, self.height, x, y, offsets);
+    }
 
-        for (x_offset, y_offset) in offsets {
-            // Convert offset position to world position
-            let x_pos: i32 = x + x_offset;
-            let y_pos: i32 = y + y_offset;
+    // The color a cell is drawn with
+    pub fn get_color(&self, index: usize) -> Color {
+        let particle: &Particle = &self.data[index];
+        let properties: &ParticleProperties = self.get_properties(particle.id);
 
-            // Add neighbor to the list if it's in grid's bounds
-            if self.is_in_bounds(x_pos, y_pos) {
-                let neighbor_index: u32 = (y_pos * self.width + x_pos) as u32;
-                neighbors.push(neighbor_index);
-            }
+        if particle.has_flag(PARTICLE_FLAG_SCORCHED) {
+            return properties.scorched_colors[particle.variant as usize];
         }
+        return properties.colors[particle.variant as usize];
+    }
 
-        return neighbors;
-    }
-    pub fn try_move_particle(
-        &mut self,
-        particle_index: usize,
-        direction_groups: &[Vec<Offset2<i32>>],
-        bump_them_nerds: bool,
-        mark_dirty: bool,
-        mark_neighbors_dirty: bool,
-    ) -> bool {
-        let current_position = self.data[particle_index].position;
-        let current_density = self.data[particle_index].density;
-        let width = self.width;
-
-        for directions in direction_groups {
-            for direction in directions {
-                // Add random 'bumps' in the x axis
-                let mut final_dx: i32 = direction.dx;
-                if bump_them_nerds {
-                    if rand::random::<f32>() > 0.5 {
-                        final_dx = -direction.dx;
-                    }
-                }
-
-                let tx: i32 = current_position.x + final_dx;
-                let ty: i32 = current_position.y + direction.dy;
+    pub fn get_pixel(&self, index: usize) -> Pixel {
+        let color: Color = self.get_color(index);
+        return Pixel::from_rgba(color.r, color.g, color.b, color.a, index);
+    }
 
-                if !self.is_in_bounds(tx, ty) {
-                    continue;
+    // Conducts heat in awake chunks and the chunks around them, returning the chunks whose temperatures were updated
+    pub fn diffuse_heat(&mut self) -> Vec<usize> {
+        // Heat leaks out of awake chunks, so their neighbors take part as well
+        let mut is_heated: Vec<bool> = vec![false; self.chunks.len()];
+        for chunk_index in self.get_awake_chunk_indices() {
+            let chunk_x: i32 = chunk_index as i32 % self.chunk_count_x;
+            let chunk_y: i32 = chunk_index as i32 / self.chunk_count_x;
+
+            for neighbor_y in (chunk_y - 1).max(0)..=(chunk_y + 1).min(self.chunk_count_y - 1) {
+                for neighbor_x in (chunk_x - 1).max(0)..=(chunk_x + 1).min(self.chunk_count_x - 1) {
+                    is_heated[(neighbor_y * self.chunk_count_x + neighbor_x) as usize] = true;
                 }
+            }
+        }
+        let heated_chunks: Vec<usize> = (0..self.chunks.len()).filter(|&chunk_index| is_heated[chunk_index]).collect();
+
+        // Exchange heat between every cell and its Moore neighbors, reading from the previous step's temperatures
+        // Nothing is written until every chunk is done, so chunks can be worked out in parallel
+        let results: Vec<(Vec<f32>, f32)> = heated_chunks.par_iter().map(|&chunk_index| self.conduct_heat_in_chunk(chunk_index)).collect();
+
+        // Write the new temperatures back in the same order they were computed
+        for (&chunk_index, (next_temperatures, largest_change)) in heated_chunks.iter().zip(results) {
+            let bounds: CellBounds = self.get_chunk_bounds(chunk_index);
+            let mut next_temperatures = next_temperatures.into_iter();
+            for y in bounds.min_y..=bounds.max_y {
+                for x in bounds.min_x..=bounds.max_x {
+                    self.temperature[(y * self.width + x) as usize] = next_temperatures.next().unwrap_or(AMBIENT_TEMPERATURE);
+                }
+            }
 
-                let target_index: usize = (ty * width + tx) as usize;
+            // Chunks that have evened out go to sleep
+            self.chunks[chunk_index].is_heat_active = largest_change > HEAT_SETTLE_THRESHOLD;
+        }
 
-                if particle_index == target_index {
-                    continue;
-                }
+        return heated_chunks;
+    }
 
-                let moved: bool = {
-                    let (a, b) = if particle_index < target_index {
-                        let (a, b) = self.data.split_at_mut(target_index);
-                        (&mut a[particle_index], &mut b[0])
-                    } else {
-                        let (a, b) = self.data.split_at_mut(particle_index);
-                        (&mut b[0], &mut a[target_index])
-                    };
-
-                    if b.is_movable && current_density > b.density {
-                        std::mem::swap(a, b);
-                        let temp_pos = a.position;
-                        a.position = b.position;
-                        b.position = temp_pos;
-
-                        let temp_index = a.index;
-                        a.index = b.index;
-                        b.index = temp_index;
-
-                        true
-                    } else {
-                        false
+    // Works out a chunk's next temperatures, row by row, along with the largest change among them
+    fn conduct_heat_in_chunk(&self, chunk_index: usize) -> (Vec<f32>, f32) {
+        let bounds: CellBounds = self.get_chunk_bounds(chunk_index);
+        let mut next_temperatures: Vec<f32> = Vec::with_capacity((CHUNK_SIZE * CHUNK_SIZE) as usize);
+        let mut largest_change: f32 = 0.0;
+
+        for y in bounds.min_y..=bounds.max_y {
+            for x in bounds.min_x..=bounds.max_x {
+                let index: usize = (y * self.width + x) as usize;
+                let properties: &ParticleProperties = self.get_properties(self.data[index].id);
+                let (conductivity, heat_capacity) = (properties.thermal_conductivity, properties.heat_capacity);
+
+                let mut heat_flow: f32 = 0.0;
+                for (x_offset, y_offset) in MOORE_NEIGHBORS {
+                    if !self.is_in_bounds(x + x_offset, y + y_offset) {
+                        continue;
                     }
-                };
 
-                if moved {
-                    if mark_dirty {
-                        self.mark_particle_dirty(tx, ty, mark_neighbors_dirty);
-                        self.mark_particle_dirty(current_position.x, current_position.y, mark_neighbors_dirty);
-                    }
-                    return true;
+                    let neighbor_index: usize = ((y + y_offset) * self.width + x + x_offset) as usize;
+                    let neighbor_conductivity: f32 = self.get_properties(self.data[neighbor_index].id).thermal_conductivity;
+                    let pair_conductivity: f32 = (conductivity + neighbor_conductivity) * 0.5;
+                    heat_flow += pair_conductivity * (self.temperature[neighbor_index] - self.temperature[index]);
                 }
+
+                let change: f32 = heat_flow / (MOORE_NEIGHBORS.len() as f32 * heat_capacity);
+                largest_change = largest_change.max(change.abs());
+                next_temperatures.push(self.temperature[index] + change);
             }
         }
 
-        return false;
+        return (next_temperatures, largest_change);
     }
 
-    pub fn fill_circle_at(&mut self, x: i32, y: i32, radius: i32, particle_id: u16) {
+    pub fn get_circle_indices(&self, x: i32, y: i32, radius: i32) -> Vec<u32> {
+        let mut indices: Vec<u32> = Vec::new();
+
         for i in -radius..radius {
             for j in -radius..radius {
                 if i * i + j * j <= radius * radius {
                     let px: i32 = x + i;
                     let py: i32 = y + j;
 
-                    // Particle to draw is out of grid's bounds, skip it
+                    // Cell is out of grid's bounds, skip it
                     if !self.is_in_bounds(px, py) {
                         continue;
                     }
 
-                    // Create particle
-                    self.create_particle_at(px, py, particle_id, true, true);
+                    indices.push((py * self.width + px) as u32);
                 }
             }
         }
+
+        return indices;
+    }
+
+    pub fn get_square_indices(&self, x: i32, y: i32, radius: i32) -> Vec<u32> {
+        return self.get_rect_indices(x - radius, y - radius, x + radius - 1, y + radius - 1);
+    }
+
+    // Cells of a filled rectangle between two opposite corners, both inclusive
+    pub fn get_rect_indices(&self, x0: i32, y0: i32, x1: i32, y1: i32) -> Vec<u32> {
+        let min_x: i32 = x0.min(x1).max(0);
+        let max_x: i32 = x0.max(x1).min(self.width - 1);
+        let min_y: i32 = y0.min(y1).max(0);
+        let max_y: i32 = y0.max(y1).min(self.height - 1);
+
+        let mut indices: Vec<u32> = Vec::new();
+        for py in min_y..=max_y {
+            for px in min_x..=max_x {
+                indices.push((py * self.width + px) as u32);
+            }
+        }
+
+        return indices;
     }
+
+    // Cells along a line between two points, using Bresenham's algorithm
+    pub fn get_line_indices(&self, x0: i32, y0: i32, x1: i32, y1: i32) -> Vec<u32> {
+        let mut indices: Vec<u32> = Vec::new();
+
+        let dx: i32 = (x1 - x0).abs();
+        let dy: i32 = -(y1 - y0).abs();
+        let step_x: i32 = if x0 < x1 { 1 } else { -1 };
+        let step_y: i32 = if y0 < y1 { 1 } else { -1 };
+        let mut error: i32 = dx + dy;
+        let (mut px, mut py) = (x0, y0);
+
+        loop {
+            if self.is_in_bounds(px, py) {
+                indices.push((py * self.width + px) as u32);
+            }
+            if px == x1 && py == y1 {
+                break;
+            }
+
+            let doubled_error: i32 = error * 2;
+            if doubled_error >= dy {
+                error += dy;
+                px += step_x;
+            }
+            if doubled_error <= dx {
+                error += dx;
+                py += step_y;
+            }
+        }
+
+        return indices;
+    }
+
+    // Cells connected to the given one through sides that hold the same particle
+    pub fn get_connected_indices(&self, x: i32, y: i32) -> Vec<u32> {
+        let target_id: u16 = match self.get_particle_at(x, y) {
+            Some(particle) => particle.id,
+            None => return Vec::new(),
+        };
+
+        let start_index: u32 = (y * self.width + x) as u32;
+        let mut visited: HashSet<u32> = HashSet::from([start_index]);
+        let mut stack: Vec<u32> = vec![start_index];
+        let mut indices: Vec<u32> = Vec::new();
+
+        while let Some(index) = stack.pop() {
+            indices.push(index);
+
+            let px: i32 = index as i32 % self.width;
+            let py: i32 = index as i32 / self.width;
+            for neighbor_index in self.get_neighbor_indices_of(px, py, &VON_NEUMANN_NEIGHBORS) {
+                if self.data[neighbor_index as usize].id == target_id && visited.insert(neighbor_index) {
+                    stack.push(neighbor_index);
+                }
+            }
+        }
+
+        return indices;
+    }
+
+    pub fn fill_circle_at(&mut self, x: i32, y: i32, radius: i32, particle_id: u16) {
+        for index in self.get_circle_indices(x, y, radius) {
+            let px: i32 = index as i32 % self.width;
+            let py: i32 = index as i32 / self.width;
+
+            // Create particle
+            self.create_particle_at(px, py, particle_id, true, true);
+        }
+    }
+}
+
+// Indices of the cells at the given offsets from a position, leaving out those outside the grid
+pub fn get_neighbor_indices(width: i32, height: i32, x: i32, y: i32, offsets: &[(i32, i32)]) -> Vec<u32> {
+    let mut neighbors: Vec<u32> = Vec::with_capacity(offsets.len());
+
+    for (x_offset, y_offset) in offsets {
+        // Convert offset position to world position
+        let x_pos: i32 = x + x_offset;
+        let y_pos: i32 = y + y_offset;
+
+        // Add neighbor to the list if it's in grid's bounds
+        if x_pos >= 0 && x_pos < width && y_pos >= 0 && y_pos < height {
+            neighbors.push((y_pos * width + x_pos) as u32);
+        }
+    }
+
+    return neighbors;
 }
@@ -0,0 +1,382 @@
+use crate::structs::chunk::{CHUNK_SIZE, Chunk, MAX_PARTICLE_REACH};
+use crate::structs::grid::{MOORE_NEIGHBORS, get_neighbor_indices};
+use crate::structs::particle::Particle;
+use crate::structs::particle_data::ParticleData;
+use crate::structs::particle_properties::ParticleProperties;
+use crate::structs::particle_registry::ParticleRegistry;
+use crate::structs::utils::{Offset2, Vector2};
+
+use rand::rngs::StdRng;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const EMPTY_PARTICLE_ID: u16 = 0;
+
+// A handle for reading and changing a grid's cells, what the physics works on
+// Handles are plain pointers into the grid, so several threads can each hold one as long as they never
+// touch the same cells, see SharedGridCells
+pub struct GridCells<'a> {
+    pub width: i32,
+    pub height: i32,
+
+    data: *mut Particle,
+    temperature: *mut f32,
+    cell_count: usize,
+
+    dirty_cells: &'a [AtomicBool],
+    chunks: &'a [Chunk],
+    chunk_count_x: i32,
+    properties: &'a [ParticleProperties],
+    registry: &'a ParticleRegistry,
+
+    _grid: PhantomData<&'a mut Particle>,
+}
+
+// A handle only lives on one thread at a time, and what it points into outlives it
+unsafe impl Send for GridCells<'_> {}
+
+impl<'a> GridCells<'a> {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        width: i32,
+        height: i32,
+        data: &'a mut [Particle],
+        temperature: &'a mut [f32],
+        dirty_cells: &'a [AtomicBool],
+        chunks: &'a [Chunk],
+        chunk_count_x: i32,
+        properties: &'a [ParticleProperties],
+        registry: &'a ParticleRegistry,
+    ) -> GridCells<'a> {
+        return GridCells {
+            width: width,
+            height: height,
+
+            cell_count: data.len().min(temperature.len()),
+            data: data.as_mut_ptr(),
+            temperature: temperature.as_mut_ptr(),
+
+            dirty_cells: dirty_cells,
+            chunks: chunks,
+            chunk_count_x: chunk_count_x,
+            properties: properties,
+            registry: registry,
+
+            _grid: PhantomData,
+        };
+    }
+
+    // --------- Cells ---------
+
+    pub fn is_in_bounds(&self, x: i32, y: i32) -> bool {
+        return x >= 0 && x < self.width && y >= 0 && y < self.height;
+    }
+
+    pub fn get_particle_data(&self, particle_id: u16) -> Option<&'a ParticleData> {
+        return self.registry.get(particle_id);
+    }
+
+    pub fn get_registry(&self) -> &'a ParticleRegistry {
+        return self.registry;
+    }
+
+    pub fn get_properties(&self, particle_id: u16) -> &'a ParticleProperties {
+        return &self.properties[(particle_id as usize).min(self.properties.len() - 1)];
+    }
+
+    // Cells don't store where they are, it follows from their index
+    pub fn get_position(&self, index: usize) -> Vector2<i32> {
+        let y: i32 = index as i32 / self.width;
+        return Vector2::<i32>::new(index as i32 - y * self.width, y);
+    }
+
+    pub fn particle(&self, index: usize) -> &Particle {
+        assert!(index < self.cell_count, "cell {} is outside the grid", index);
+        // Borrowing the handle keeps this from overlapping with a mutable borrow of the same cell
+        return unsafe { &*self.data.add(index) };
+    }
+
+    pub fn particle_mut(&mut self, index: usize) -> &mut Particle {
+        assert!(index < self.cell_count, "cell {} is outside the grid", index);
+        return unsafe { &mut *self.data.add(index) };
+    }
+
+    pub fn get_particle_at(&self, x: i32, y: i32) -> Option<&Particle> {
+        if !self.is_in_bounds(x, y) {
+            return None;
+        }
+        return Some(self.particle((y * self.width + x) as usize));
+    }
+
+    pub fn get_temperature(&self, index: usize) -> f32 {
+        assert!(index < self.cell_count, "cell {} is outside the grid", index);
+        return unsafe { *self.temperature.add(index) };
+    }
+
+    pub fn set_temperature(&mut self, index: usize, temperature: f32) {
+        assert!(index < self.cell_count, "cell {} is outside the grid", index);
+        unsafe { *self.temperature.add(index) = temperature };
+    }
+
+    pub fn get_neighbor_indices_of(&self, x: i32, y: i32, offsets: &[(i32, i32)]) -> Vec<u32> {
+        return get_neighbor_indices(self.width, self.height, x, y, offsets);
+    }
+
+    // --------- Dirty Cells ---------
+
+    pub fn mark_particle_dirty(&mut self, x: i32, y: i32, mark_neighbors_dirty: bool) {
+        self.mark_cell_dirty(x, y);
+
+        // Mark particle's neighbors dirty
+        if mark_neighbors_dirty {
+            for (x_offset, y_offset) in MOORE_NEIGHBORS {
+                if self.is_in_bounds(x + x_offset, y + y_offset) {
+                    self.mark_cell_dirty(x + x_offset, y + y_offset);
+                }
+            }
+        }
+    }
+
+    fn mark_cell_dirty(&mut self, x: i32, y: i32) {
+        let index: usize = (y * self.width + x) as usize;
+        if self.dirty_cells[index].swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        let chunk_index: usize = ((y / CHUNK_SIZE) * self.chunk_count_x + x / CHUNK_SIZE) as usize;
+        self.chunks[chunk_index].mark_dirty(x, y);
+    }
+
+    // --------- Methods ---------
+
+    pub fn create_particle_at(
+        &mut self,
+        rng: &mut StdRng,
+        x: i32,
+        y: i32,
+        particle_id: u16,
+        mark_dirty: bool,
+        mark_neighbors_dirty: bool,
+    ) -> bool {
+        // x and y axis are out of bounds, return
+        if !(self.is_in_bounds(x, y)) {
+            return false;
+        }
+
+        // Retrieve particle data
+        let particle_data: &ParticleData = match self.registry.get(particle_id) {
+            Some(val) => val,
+            None => return false, // Cannot create particle, invalid particle id, return
+        };
+
+        // Instanciate a new particle
+        let new_particle: Particle = Particle::new(particle_data, rng);
+
+        // Assigning the new particle and its starting temperature to the data
+        let index: usize = (y * self.width + x) as usize;
+        *self.particle_mut(index) = new_particle;
+        self.set_temperature(index, particle_data.temperature);
+
+        // Handle dirty logic
+        if mark_dirty {
+            self.mark_particle_dirty(x, y, mark_neighbors_dirty);
+        }
+
+        return true;
+    }
+
+    // Tries each direction in turn, moving into the first cell the particle can displace
+    pub fn try_move_particle(
+        &mut self,
+        particle_index: usize,
+        direction_groups: &[Vec<Offset2<i32>>],
+        mark_dirty: bool,
+        mark_neighbors_dirty: bool,
+    ) -> bool {
+        let current_position: Vector2<i32> = self.get_position(particle_index);
+        let current_density: f32 = self.get_properties(self.particle(particle_index).id).density;
+        let width = self.width;
+
+        for directions in direction_groups {
+            for direction in directions {
+                let tx: i32 = current_position.x + direction.dx;
+                let ty: i32 = current_position.y + direction.dy;
+
+                if !self.is_in_bounds(tx, ty) {
+                    continue;
+                }
+
+                let target_index: usize = (ty * width + tx) as usize;
+
+                if particle_index == target_index {
+                    continue;
+                }
+
+                // Chunks update in parallel on the promise that no move reaches past MAX_PARTICLE_REACH
+                debug_assert!(
+                    direction.dx.abs() < MAX_PARTICLE_REACH && direction.dy.abs() < MAX_PARTICLE_REACH,
+                    "move by ({}, {}) reaches past MAX_PARTICLE_REACH",
+                    direction.dx,
+                    direction.dy
+                );
+
+                let target: &ParticleProperties = self.get_properties(self.particle(target_index).id);
+                if !(target.is_movable && current_density > target.density) {
+                    continue;
+                }
+
+                self.swap_cells(particle_index, target_index);
+
+                if mark_dirty {
+                    self.mark_particle_dirty(tx, ty, mark_neighbors_dirty);
+                    self.mark_particle_dirty(current_position.x, current_position.y, mark_neighbors_dirty);
+                }
+                return true;
+            }
+        }
+
+        return false;
+    }
+
+    // Moves a particle up to the whole offset in one go, passing through every cell on the way
+    // Stops in front of the first cell it can't displace so fast particles never tunnel through walls,
+    // and inside the first occupied cell it pushes aside, since nothing keeps its speed through a liquid
+    // Returns where the particle ended up and how many cells it moved
+    pub fn try_move_particle_along(
+        &mut self,
+        particle_index: usize,
+        offset: Offset2<i32>,
+        mark_dirty: bool,
+        mark_neighbors_dirty: bool,
+    ) -> (usize, i32) {
+        let start_position: Vector2<i32> = self.get_position(particle_index);
+        let step_count: i32 = offset.dx.abs().max(offset.dy.abs());
+        // However far the offset asks for, the particle never leaves what its chunk's update may touch
+        let max_steps: i32 = step_count.min(MAX_PARTICLE_REACH - 1);
+        let width = self.width;
+
+        // Walk the line one cell at a time, the longer axis moves every step
+        let mut current_index: usize = particle_index;
+        let mut current_position: Vector2<i32> = start_position;
+        let mut steps_taken: i32 = 0;
+        for step in 1..=max_steps {
+            let tx: i32 = start_position.x + (offset.dx as f32 * step as f32 / step_count as f32).round() as i32;
+            let ty: i32 = start_position.y + (offset.dy as f32 * step as f32 / step_count as f32).round() as i32;
+
+            if !self.can_displace(current_index, tx, ty) {
+                break;
+            }
+
+            let target_index: usize = (ty * width + tx) as usize;
+            let is_target_empty: bool = self.particle(target_index).id == EMPTY_PARTICLE_ID;
+            self.swap_cells(current_index, target_index);
+
+            // Whatever got pushed aside now sits in the cell just left behind
+            if mark_dirty {
+                self.mark_particle_dirty(current_position.x, current_position.y, mark_neighbors_dirty);
+            }
+
+            current_index = target_index;
+            current_position = Vector2::<i32>::new(tx, ty);
+            steps_taken += 1;
+
+            if !is_target_empty {
+                break;
+            }
+        }
+
+        debug_assert!(
+            (current_position.x - start_position.x).abs() < MAX_PARTICLE_REACH
+                && (current_position.y - start_position.y).abs() < MAX_PARTICLE_REACH,
+            "move from {:?} to {:?} reaches past MAX_PARTICLE_REACH",
+            start_position,
+            current_position
+        );
+
+        if mark_dirty && steps_taken > 0 {
+            self.mark_particle_dirty(current_position.x, current_position.y, mark_neighbors_dirty);
+        }
+
+        return (current_index, steps_taken);
+    }
+
+    // Swap two cells, heat travels with the particles that carry it
+    fn swap_cells(&mut self, index_a: usize, index_b: usize) {
+        assert!(index_a < self.cell_count && index_b < self.cell_count);
+        assert!(index_a != index_b);
+
+        // The indices differ, so the two references never overlap
+        unsafe {
+            let a: &mut Particle = &mut *self.data.add(index_a);
+            let b: &mut Particle = &mut *self.data.add(index_b);
+            std::mem::swap(a, b);
+
+            std::ptr::swap(self.temperature.add(index_a), self.temperature.add(index_b));
+        }
+    }
+
+    pub fn can_displace(&self, particle_index: usize, x: i32, y: i32) -> bool {
+        let current_density: f32 = self.get_properties(self.particle(particle_index).id).density;
+
+        // A particle can only push aside movable particles lighter than itself
+        return match self.get_particle_at(x, y) {
+            Some(target) => {
+                let target: &ParticleProperties = self.get_properties(target.id);
+                target.is_movable && current_density > target.density
+            }
+            None => false,
+        };
+    }
+
+    pub fn get_free_distance(&self, particle_index: usize, step: Offset2<i32>, max_steps: i32) -> i32 {
+        let current_position: Vector2<i32> = self.get_position(particle_index);
+
+        // Walk along the step direction until a cell that can't be displaced is found
+        let mut distance: i32 = 0;
+        while distance < max_steps {
+            let tx: i32 = current_position.x + step.dx * (distance + 1);
+            let ty: i32 = current_position.y + step.dy * (distance + 1);
+
+            if !self.can_displace(particle_index, tx, ty) {
+                break;
+            }
+            distance += 1;
+        }
+
+        return distance;
+    }
+}
+
+// Lets threads each take their own handle onto the same grid
+pub struct SharedGridCells<'a> {
+    cells: GridCells<'a>,
+}
+
+unsafe impl Sync for SharedGridCells<'_> {}
+
+impl<'a> SharedGridCells<'a> {
+    pub fn new(cells: GridCells<'a>) -> SharedGridCells<'a> {
+        return SharedGridCells { cells: cells };
+    }
+
+    /// # Safety
+    /// Handles that are in use at the same time must never read or write the same cell
+    pub unsafe fn handle(&self) -> GridCells<'a> {
+        return GridCells {
+            width: self.cells.width,
+            height: self.cells.height,
+
+            data: self.cells.data,
+            temperature: self.cells.temperature,
+            cell_count: self.cells.cell_count,
+
+            dirty_cells: self.cells.dirty_cells,
+            chunks: self.cells.chunks,
+            chunk_count_x: self.cells.chunk_count_x,
+            properties: self.cells.properties,
+            registry: self.cells.registry,
+
+            _grid: PhantomData,
+        };
+    }
+}
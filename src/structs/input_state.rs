@@ -4,4 +4,18 @@ pub struct InputState {
     pub mouse_position: Vector2<f64>,
     pub mouse_left_down: bool,
     pub mouse_right_down: bool,
+
+    // Where the mouse was when the engine last painted, None outside of a stroke
+    pub previous_mouse_position: Option<Vector2<f64>>,
+}
+
+impl Default for InputState {
+    fn default() -> InputState {
+        return InputState {
+            mouse_position: Vector2::<f64>::new(0.0, 0.0),
+            mouse_left_down: false,
+            mouse_right_down: false,
+            previous_mouse_position: None,
+        };
+    }
 }
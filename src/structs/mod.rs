@@ -1,6 +1,12 @@
+pub mod chunk;
 pub mod color;
+pub mod gravity;
 pub mod grid;
+pub mod grid_cells;
 pub mod input_state;
 pub mod particle;
 pub mod particle_data;
+pub mod particle_properties;
+pub mod particle_registry;
+pub mod reaction;
 pub mod utils;
@@ -1,46 +1,69 @@
-use crate::structs::color::Color;
 use crate::structs::particle_data::ParticleData;
 use crate::structs::utils::Vector2;
-use rand::{Rng, random};
+use rand::Rng;
 
-#[derive(Debug, Clone)]
+// Number of discrete colors a particle can pick between its base and variant colors
+pub const COLOR_VARIANT_COUNT: u8 = 6;
+
+// Fastest a particle moves along either axis, in cells per tick
+pub const TERMINAL_VELOCITY: f32 = 8.0;
+
+// Flags a particle can carry on top of its ID
+pub const PARTICLE_FLAG_SCORCHED: u8 = 1 << 0; // Caught fire, drawn tinted with the flame's color
+
+// Everything about the particle in one cell that isn't the same for every particle of its kind
+// What a particle is made of (density, category, colors, ...) is looked up by ID instead of being copied into every cell
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub struct Particle {
-    pub handle: u32,
     pub id: u16,
-    pub name: String,
-    pub category: u16,
-    pub color: Color,
-    pub position: Vector2<i32>,
-    pub index: u32,
-    pub is_movable: bool,
-    pub density: f32,
+    pub variant: u8,
+    pub flags: u8,
+    pub age: u32,
+    pub burn_timer: u32,
+
+    // Cells per tick, y points up like the grid does
+    // Only set through set_velocity, which keeps it within terminal velocity
+    velocity: Vector2<f32>,
 }
 
 impl Particle {
-    pub fn new(particle_data: &ParticleData, x: i32, y: i32, handle: u32) -> Particle {
-        // Get a random number between 0 and 1 for lerp alpha
-        let alpha: f32 = rand::rng().random_range(0.0..1.0);
-
-        // Quantize alpha to the nearest discrete 6 steps
-        let resolution: f32 = 6.0;
-        let steps: f32 = resolution - 1.0;
-        let t: f32 = (alpha * steps).round() / steps;
-
-        // Get a color for this particle between it's base and variant colors based on the random alpha
-        let final_color: Color = Color::lerp_hex(&particle_data.base_color, &particle_data.variant_color, t);
+    pub fn new(particle_data: &ParticleData, rng: &mut impl Rng) -> Particle {
+        // Pick one of the discrete color variants at random, single colored particles always use the first
+        let variant: u8 = if particle_data.base_color == particle_data.variant_color {
+            0
+        } else {
+            rng.random_range(0..COLOR_VARIANT_COUNT)
+        };
+        return Self::with_variant(particle_data, variant);
+    }
 
+    pub fn with_variant(particle_data: &ParticleData, variant: u8) -> Particle {
         // Create and return particle struct
         let new_particle: Particle = Particle {
-            handle: handle,
             id: particle_data.id,
-            name: particle_data.name.clone(),
-            category: particle_data.category,
-            color: final_color,
-            position: Vector2::<i32>::new(x, y),
-            index: 0,
-            is_movable: particle_data.is_movable,
-            density: particle_data.density,
+            variant: variant.min(COLOR_VARIANT_COUNT - 1),
+            flags: 0,
+            age: 0,
+            // Fire (category 5) is born burning, everything else has to be ignited
+            burn_timer: if particle_data.category == 5 { particle_data.burn_time.max(1) } else { 0 },
+            velocity: Vector2::<f32>::new(0.0, 0.0),
         };
         return new_particle;
     }
+
+    pub fn has_flag(&self, flag: u8) -> bool {
+        return self.flags & flag != 0;
+    }
+
+    pub fn get_velocity(&self) -> Vector2<f32> {
+        return self.velocity;
+    }
+
+    // Limits each axis to terminal velocity, a faster particle would move further than a chunk update may reach
+    pub fn set_velocity(&mut self, velocity: Vector2<f32>) {
+        self.velocity = Vector2::<f32>::new(
+            velocity.x.clamp(-TERMINAL_VELOCITY, TERMINAL_VELOCITY),
+            velocity.y.clamp(-TERMINAL_VELOCITY, TERMINAL_VELOCITY),
+        );
+    }
 }
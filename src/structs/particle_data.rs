@@ -7,4 +7,17 @@ pub struct ParticleData {
     pub variant_color: String,
     pub is_movable: bool,
     pub density: f32,
+    pub dispersion: u16,
+    pub lifetime: u32,
+    pub repose_angle: f32,
+    pub temperature: f32,
+    pub thermal_conductivity: f32,
+    pub heat_capacity: f32,
+    pub melt_point: Option<f32>,
+    pub melt_into: Option<u16>,
+    pub boil_point: Option<f32>,
+    pub boil_into: Option<u16>,
+    pub flammability: f32,
+    pub burn_time: u32,
+    pub burn_into: u16,
 }
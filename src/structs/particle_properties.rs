@@ -0,0 +1,72 @@
+use crate::structs::color::Color;
+use crate::structs::particle::COLOR_VARIANT_COUNT;
+use crate::structs::particle_data::ParticleData;
+use crate::structs::particle_registry::ParticleRegistry;
+
+// How strongly scorched particles are tinted with the flame's color
+const SCORCH_TINT: f32 = 0.6;
+
+// The parts of a particle's data the simulation reads every tick, flattened so they can be looked up by ID in a table
+#[derive(Debug, Clone, Copy)]
+pub struct ParticleProperties {
+    pub category: u16,
+    pub density: f32,
+    pub is_movable: bool,
+    pub thermal_conductivity: f32,
+    pub heat_capacity: f32,
+
+    // Every color variant worked out once, plain and scorched
+    pub colors: [Color; COLOR_VARIANT_COUNT as usize],
+    pub scorched_colors: [Color; COLOR_VARIANT_COUNT as usize],
+}
+
+impl Default for ParticleProperties {
+    // Unknown IDs don't move, don't conduct and show up bright magenta
+    fn default() -> ParticleProperties {
+        let missing_color: Color = Color::from_rgba(255, 0, 255, 255);
+        return ParticleProperties {
+            category: 0,
+            density: 0.0,
+            is_movable: false,
+            thermal_conductivity: 0.0,
+            heat_capacity: 1.0,
+
+            colors: [missing_color; COLOR_VARIANT_COUNT as usize],
+            scorched_colors: [missing_color; COLOR_VARIANT_COUNT as usize],
+        };
+    }
+}
+
+impl ParticleProperties {
+    pub fn from_data(particle_data: &ParticleData, flame_color: &Color) -> ParticleProperties {
+        // Turn each variant into a lerp alpha between the base and variant colors
+        let steps: f32 = (COLOR_VARIANT_COUNT - 1) as f32;
+        let colors: [Color; COLOR_VARIANT_COUNT as usize] = std::array::from_fn(|variant| {
+            Color::lerp_hex(&particle_data.base_color, &particle_data.variant_color, variant as f32 / steps)
+        });
+
+        return ParticleProperties {
+            category: particle_data.category,
+            density: particle_data.density,
+            is_movable: particle_data.is_movable,
+            thermal_conductivity: particle_data.thermal_conductivity,
+            heat_capacity: particle_data.heat_capacity,
+
+            colors: colors,
+            scorched_colors: colors.map(|color| Color::lerp_color(&color, flame_color, SCORCH_TINT)),
+        };
+    }
+
+    // One entry per ID up to the largest one in the registry, IDs without particle data get the defaults
+    pub fn build_table(registry: &ParticleRegistry) -> Vec<ParticleProperties> {
+        let flame_color: Color = registry.get_flame_color();
+
+        let max_particle_id: usize = registry.particles.keys().cloned().max().unwrap_or(0) as usize;
+        let mut table: Vec<ParticleProperties> = vec![ParticleProperties::default(); max_particle_id + 1];
+        for (id, particle_data) in &registry.particles {
+            table[*id as usize] = ParticleProperties::from_data(particle_data, &flame_color);
+        }
+
+        return table;
+    }
+}
@@ -0,0 +1,137 @@
+use crate::structs::color::Color;
+use crate::structs::particle_data::ParticleData;
+use crate::structs::reaction::Reaction;
+use std::collections::HashMap;
+
+const FIRE_PARTICLE_ID: u16 = 400;
+
+#[derive(Debug, Clone)]
+pub struct ParticleRegistry {
+    pub particles: HashMap<u16, ParticleData>,
+    pub reactions: Vec<Reaction>,
+}
+
+impl ParticleRegistry {
+    pub fn get(&self, particle_id: u16) -> Option<&ParticleData> {
+        return self.particles.get(&particle_id);
+    }
+
+    // Finds a reaction between two particles, oriented so that reactant_a is the first particle
+    pub fn find_reaction(&self, particle_id: u16, other_id: u16) -> Option<Reaction> {
+        for reaction in &self.reactions {
+            if reaction.reactant_a == particle_id && reaction.reactant_b == other_id {
+                return Some(*reaction);
+            }
+            if reaction.reactant_b == particle_id && reaction.reactant_a == other_id {
+                return Some(reaction.flipped());
+            }
+        }
+
+        return None;
+    }
+
+    // Color burning particles are tinted with
+    pub fn get_flame_color(&self) -> Color {
+        return match self.get(FIRE_PARTICLE_ID) {
+            Some(fire_data) => Color::from_hex(&fire_data.base_color),
+            None => Color::from_rgba(255, 120, 30, 255),
+        };
+    }
+
+    // Hashes every particle and reaction so saves made with a different set of particle data can be detected
+    // Fields are fed one by one in a fixed order, so the hash only changes when the data itself does
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher: ContentHasher = ContentHasher::new();
+
+        let mut particle_ids: Vec<&u16> = self.particles.keys().collect();
+        particle_ids.sort();
+        for id in particle_ids {
+            let data: &ParticleData = &self.particles[id];
+            hasher.write_u16(data.id);
+            hasher.write_str(&data.name);
+            hasher.write_u16(data.category);
+            hasher.write_str(&data.base_color);
+            hasher.write_str(&data.variant_color);
+            hasher.write_bool(data.is_movable);
+            hasher.write_f32(data.density);
+            hasher.write_u16(data.dispersion);
+            hasher.write_u32(data.lifetime);
+            hasher.write_f32(data.repose_angle);
+            hasher.write_f32(data.temperature);
+            hasher.write_f32(data.thermal_conductivity);
+            hasher.write_f32(data.heat_capacity);
+            hasher.write_optional_f32(data.melt_point);
+            hasher.write_optional_u16(data.melt_into);
+            hasher.write_optional_f32(data.boil_point);
+            hasher.write_optional_u16(data.boil_into);
+            hasher.write_f32(data.flammability);
+            hasher.write_u32(data.burn_time);
+            hasher.write_u16(data.burn_into);
+        }
+
+        for reaction in &self.reactions {
+            hasher.write_u16(reaction.reactant_a);
+            hasher.write_u16(reaction.reactant_b);
+            hasher.write_u16(reaction.product_a);
+            hasher.write_u16(reaction.product_b);
+            hasher.write_f32(reaction.probability);
+        }
+
+        return hasher.hash;
+    }
+}
+
+// FNV-1a over little-endian bytes, std's hasher isn't guaranteed to be stable between builds
+struct ContentHasher {
+    hash: u64,
+}
+
+impl ContentHasher {
+    fn new() -> ContentHasher {
+        return ContentHasher { hash: 0xCBF2_9CE4_8422_2325 };
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    }
+
+    fn write_bool(&mut self, value: bool) {
+        self.write_bytes(&[value as u8]);
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write_bytes(&value.to_le_bytes());
+    }
+
+    fn write_f32(&mut self, value: f32) {
+        self.write_bytes(&value.to_bits().to_le_bytes());
+    }
+
+    // Strings are length prefixed, so neighboring strings can't run into each other
+    fn write_str(&mut self, value: &str) {
+        self.write_u32(value.len() as u32);
+        self.write_bytes(value.as_bytes());
+    }
+
+    // Optional values are tagged, so a missing value never hashes like a present one
+    fn write_optional_f32(&mut self, value: Option<f32>) {
+        self.write_bool(value.is_some());
+        if let Some(value) = value {
+            self.write_f32(value);
+        }
+    }
+
+    fn write_optional_u16(&mut self, value: Option<u16>) {
+        self.write_bool(value.is_some());
+        if let Some(value) = value {
+            self.write_u16(value);
+        }
+    }
+}
@@ -0,0 +1,21 @@
+#[derive(Debug, Clone, Copy)]
+pub struct Reaction {
+    pub reactant_a: u16,
+    pub reactant_b: u16,
+    pub product_a: u16,
+    pub product_b: u16,
+    pub probability: f32,
+}
+
+impl Reaction {
+    // Returns the same reaction with its reactants, and products, swapped around
+    pub fn flipped(&self) -> Reaction {
+        return Reaction {
+            reactant_a: self.reactant_b,
+            reactant_b: self.reactant_a,
+            product_a: self.product_b,
+            product_b: self.product_a,
+            probability: self.probability,
+        };
+    }
+}
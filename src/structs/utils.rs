@@ -1,6 +1,6 @@
 // --------- Structs ---------
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub struct Vector2<T> {
     pub x: T,
     pub y: T,
@@ -0,0 +1,94 @@
+mod common;
+
+use bog_engine::core::engine::Engine;
+use bog_engine::io::null_renderer::NullRenderer;
+use bog_engine::structs::chunk::CHUNK_SIZE;
+use bog_engine::structs::grid::AMBIENT_TEMPERATURE;
+use bog_engine::structs::input_state::InputState;
+
+use common::{SAND_PARTICLE_ID, STONE_PARTICLE_ID, create_seeded_engine};
+
+const GRID_WIDTH: usize = 256;
+const GRID_HEIGHT: usize = 192;
+
+fn run_until_asleep(engine: &mut Engine, renderer: &mut NullRenderer, max_ticks: usize) -> bool {
+    let mut input_state: InputState = InputState::default();
+    for _ in 0..max_ticks {
+        engine.update(renderer, &mut input_state);
+        if engine.current_grid.get_awake_chunk_indices().is_empty() {
+            return true;
+        }
+    }
+    return false;
+}
+
+#[test]
+fn grid_is_split_into_chunks() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let engine: Engine = create_seeded_engine(GRID_WIDTH, GRID_HEIGHT, 7, &mut renderer);
+
+    assert_eq!(engine.current_grid.get_chunk_count_x(), GRID_WIDTH as i32 / CHUNK_SIZE);
+    assert_eq!(engine.current_grid.get_chunk_count_y(), GRID_HEIGHT as i32 / CHUNK_SIZE);
+
+    // A fresh, empty world has nothing to do
+    assert!(engine.current_grid.get_awake_chunk_indices().is_empty());
+}
+
+#[test]
+fn settled_worlds_fall_asleep() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_seeded_engine(GRID_WIDTH, GRID_HEIGHT, 7, &mut renderer);
+
+    for x in 100..140 {
+        engine.current_grid.create_particle_at(x, 40, SAND_PARTICLE_ID, true, true);
+    }
+    assert!(!engine.current_grid.get_awake_chunk_indices().is_empty());
+    assert!(run_until_asleep(&mut engine, &mut renderer, 500));
+
+    // Sleeping chunks aren't touched, so an update has nothing to draw
+    let queued_before: u64 = renderer.particles_queued;
+    let mut input_state: InputState = InputState::default();
+    engine.update(&mut renderer, &mut input_state);
+    assert_eq!(renderer.particles_queued, queued_before);
+}
+
+#[test]
+fn changes_only_wake_nearby_chunks() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_seeded_engine(GRID_WIDTH, GRID_HEIGHT, 7, &mut renderer);
+
+    // A cell in the middle of a chunk wakes that chunk alone
+    engine.current_grid.create_particle_at(48, 48, STONE_PARTICLE_ID, true, true);
+    let awake_chunks: Vec<usize> = engine.current_grid.get_awake_chunk_indices();
+    assert_eq!(awake_chunks, vec![engine.current_grid.get_chunk_index_at(48, 48)]);
+
+    // Its neighbors spill over into the chunks around a corner
+    engine.current_grid.create_particle_at(CHUNK_SIZE * 3, CHUNK_SIZE * 3, STONE_PARTICLE_ID, true, true);
+    assert_eq!(engine.current_grid.get_awake_chunk_indices().len(), 5);
+}
+
+#[test]
+fn heat_keeps_spreading_through_still_material() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_seeded_engine(GRID_WIDTH, GRID_HEIGHT, 7, &mut renderer);
+
+    // A stone bar that never moves, heated just short of the next chunk
+    for x in 10..80 {
+        engine.current_grid.create_particle_at(x, 10, STONE_PARTICLE_ID, true, true);
+    }
+    for x in 24..28 {
+        engine.current_grid.temperature[10 * GRID_WIDTH + x] = 2000.0;
+    }
+
+    // The heat crosses into the next chunk even though no particle is dirty anymore
+    let far_index: usize = 10 * GRID_WIDTH + (CHUNK_SIZE as usize + 4);
+    let mut input_state: InputState = InputState::default();
+    for _ in 0..400 {
+        engine.update(&mut renderer, &mut input_state);
+    }
+    assert!(!engine.current_grid.has_dirty_particles());
+    assert!(engine.current_grid.temperature[far_index] > AMBIENT_TEMPERATURE + 10.0);
+
+    // Once it has evened out the bar goes to sleep
+    assert!(run_until_asleep(&mut engine, &mut renderer, 20000));
+}
@@ -0,0 +1,67 @@
+// Fixtures shared by the integration tests, every test file only uses some of them
+#![allow(dead_code)]
+
+use bog_engine::core::engine::Engine;
+use bog_engine::io::renderer_interface::RendererInterface;
+use bog_engine::settings::Settings;
+use bog_engine::structs::input_state::InputState;
+use bog_engine::structs::utils::Vector2;
+
+pub const STONE_PARTICLE_ID: u16 = 10;
+pub const WOOD_PARTICLE_ID: u16 = 12;
+pub const WATER_PARTICLE_ID: u16 = 100;
+pub const LAVA_PARTICLE_ID: u16 = 102;
+pub const SAND_PARTICLE_ID: u16 = 300;
+pub const FIRE_PARTICLE_ID: u16 = 400;
+
+// Every engine starts from a fixed seed, so a failing test fails the same way on every run
+pub const DEFAULT_SEED: u64 = 1;
+
+// Default settings for a world of the given size, without the debug overlay
+pub fn create_settings(game_width: usize, game_height: usize) -> Settings {
+    return Settings {
+        game_width: game_width,
+        game_height: game_height,
+        debug_enabled: false,
+        seed: Some(DEFAULT_SEED),
+        ..Settings::default()
+    };
+}
+
+pub fn create_engine(game_width: usize, game_height: usize, renderer: &mut dyn RendererInterface) -> Engine {
+    return create_seeded_engine(game_width, game_height, DEFAULT_SEED, renderer);
+}
+
+pub fn create_seeded_engine(game_width: usize, game_height: usize, seed: u64, renderer: &mut dyn RendererInterface) -> Engine {
+    let settings: Settings = Settings {
+        seed: Some(seed),
+        ..create_settings(game_width, game_height)
+    };
+    return Engine::new(&settings, renderer).unwrap();
+}
+
+pub fn run_ticks(engine: &mut Engine, renderer: &mut dyn RendererInterface, ticks: usize) {
+    let mut input_state: InputState = InputState::default();
+    for _ in 0..ticks {
+        engine.update(renderer, &mut input_state);
+    }
+}
+
+// Holds the left mouse button down along the given points, then releases it
+pub fn drag(engine: &mut Engine, renderer: &mut dyn RendererInterface, points: &[(f64, f64)]) {
+    let mut input_state: InputState = InputState {
+        mouse_left_down: true,
+        ..InputState::default()
+    };
+    for &(x, y) in points {
+        input_state.mouse_position = Vector2::<f64>::new(x, y);
+        engine.update(renderer, &mut input_state);
+    }
+
+    input_state.mouse_left_down = false;
+    engine.update(renderer, &mut input_state);
+}
+
+pub fn count_particles(engine: &Engine, particle_id: u16) -> usize {
+    return engine.current_grid.data.iter().filter(|particle| particle.id == particle_id).count();
+}
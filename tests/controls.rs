@@ -0,0 +1,93 @@
+mod common;
+
+use bog_engine::core::engine::Engine;
+use bog_engine::core::key_bindings::{Action, KeyBinding, KeyBindings};
+use bog_engine::io::null_renderer::NullRenderer;
+use bog_engine::settings::Settings;
+use bog_engine::structs::input_state::InputState;
+
+use winit::keyboard::KeyCode;
+
+use common::{SAND_PARTICLE_ID, create_engine};
+
+#[test]
+fn shipped_bindings_match_defaults() {
+    let settings: Settings = Settings::default();
+    let (key_bindings, diagnostics) = KeyBindings::load(&settings.key_bindings_path).unwrap();
+    let default_bindings: KeyBindings = KeyBindings::default();
+
+    assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    for action in [Action::SelectParticleSlot(0), Action::NextParticle, Action::TogglePause, Action::ToggleZeroGravity, Action::Redo, Action::Snapshot] {
+        let mut file_keys: Vec<String> = key_bindings.get_bindings_for(action).iter().map(|binding| format!("{:?}", binding)).collect();
+        let mut default_keys: Vec<String> = default_bindings.get_bindings_for(action).iter().map(|binding| format!("{:?}", binding)).collect();
+        file_keys.sort();
+        default_keys.sort();
+        assert_eq!(file_keys, default_keys);
+    }
+}
+
+#[test]
+fn bindings_file_remaps_actions() {
+    let text: &str = "toggle_pause: KeyP\nundo: Ctrl+Shift+KeyU\nstep: NotAKey\nfly: Space\n";
+    let (key_bindings, diagnostics) = KeyBindings::parse(text);
+
+    // The remapped action loses its default key
+    assert_eq!(key_bindings.get_action(&KeyBinding::new(KeyCode::KeyP)), Some(Action::TogglePause));
+    assert_eq!(key_bindings.get_action(&KeyBinding::new(KeyCode::Space)), None);
+    let ctrl_shift_u: KeyBinding = KeyBinding {
+        ctrl: true,
+        shift: true,
+        ..KeyBinding::new(KeyCode::KeyU)
+    };
+    assert_eq!(key_bindings.get_action(&ctrl_shift_u), Some(Action::Undo));
+
+    // Bad lines are reported, and the actions they name keep their defaults
+    assert_eq!(diagnostics.iter().map(|diagnostic| diagnostic.line).collect::<Vec<usize>>(), vec![3, 4]);
+    assert_eq!(key_bindings.get_action(&KeyBinding::new(KeyCode::Period)), Some(Action::Step));
+}
+
+#[test]
+fn selection_cycles_through_registry() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_engine(32, 32, &mut renderer);
+    let particle_ids: Vec<u16> = engine.get_selectable_particle_ids();
+
+    assert!(engine.select_particle_slot(0));
+    assert_eq!(engine.selected_particle_id, particle_ids[0]);
+    assert!(!engine.select_particle_slot(particle_ids.len()));
+
+    // Going back from the first particle wraps around to the last
+    engine.cycle_selected_particle(-1);
+    assert_eq!(engine.selected_particle_id, *particle_ids.last().unwrap());
+    engine.cycle_selected_particle(1);
+    assert_eq!(engine.selected_particle_id, particle_ids[0]);
+
+    engine.resize_brush(-100);
+    assert_eq!(engine.brush_radius, 1);
+}
+
+#[test]
+fn pause_holds_the_world_until_stepped() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_engine(32, 32, &mut renderer);
+    let mut input_state: InputState = InputState::default();
+
+    engine.toggle_pause();
+    engine.current_grid.create_particle_at(10, 20, SAND_PARTICLE_ID, true, true);
+    for _ in 0..10 {
+        engine.update(&mut renderer, &mut input_state);
+    }
+    assert_eq!(engine.current_grid.get_particle_at(10, 20).unwrap().id, SAND_PARTICLE_ID);
+
+    // A single step moves the grain by a single cell
+    engine.request_step();
+    engine.update(&mut renderer, &mut input_state);
+    engine.update(&mut renderer, &mut input_state);
+    assert_eq!(engine.current_grid.get_particle_at(10, 19).unwrap().id, SAND_PARTICLE_ID);
+
+    engine.toggle_pause();
+    for _ in 0..40 {
+        engine.update(&mut renderer, &mut input_state);
+    }
+    assert_eq!(engine.current_grid.get_particle_at(10, 0).unwrap().id, SAND_PARTICLE_ID);
+}
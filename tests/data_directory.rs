@@ -0,0 +1,24 @@
+// Kept in its own test binary, changing the working directory would trip up tests running alongside it
+use bog_engine::core::key_bindings::KeyBindings;
+use bog_engine::loader::load_particle_data_sources;
+use bog_engine::settings::Settings;
+
+use std::path::PathBuf;
+
+#[test]
+fn built_in_data_is_found_from_any_working_directory() {
+    let working_directory: PathBuf = std::env::temp_dir().join(format!("bog_working_directory_{}", std::process::id()));
+    std::fs::create_dir_all(&working_directory).unwrap();
+    std::env::set_current_dir(&working_directory).unwrap();
+
+    let settings: Settings = Settings::default();
+    let particle_data_loaded: bool = load_particle_data_sources(&settings.particle_data_paths).is_ok();
+    let key_bindings_loaded: bool = KeyBindings::load(&settings.key_bindings_path).is_ok();
+
+    std::env::set_current_dir(std::env::temp_dir()).unwrap();
+    std::fs::remove_dir(&working_directory).unwrap();
+
+    assert!(settings.particle_data_paths[0].is_absolute());
+    assert!(particle_data_loaded);
+    assert!(key_bindings_loaded);
+}
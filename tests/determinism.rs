@@ -0,0 +1,153 @@
+mod common;
+
+use bog_engine::core::engine::Engine;
+use bog_engine::io::null_renderer::NullRenderer;
+use bog_engine::structs::input_state::InputState;
+use bog_engine::structs::utils::Vector2;
+
+use common::{FIRE_PARTICLE_ID, LAVA_PARTICLE_ID, SAND_PARTICLE_ID, WATER_PARTICLE_ID, WOOD_PARTICLE_ID, create_seeded_engine};
+
+const GRID_WIDTH: usize = 48;
+const GRID_HEIGHT: usize = 40;
+const TICK_COUNT: usize = 300;
+
+const METHANE_PARTICLE_ID: u16 = 202;
+
+// One step of the input script, the particle is painted along the given mouse positions
+struct ScriptedStroke {
+    tool_name: &'static str,
+    particle_id: u16,
+    points: &'static [(f64, f64)],
+}
+
+const INPUT_SCRIPT: [ScriptedStroke; 6] = [
+    ScriptedStroke {
+        tool_name: "Rectangle",
+        particle_id: WOOD_PARTICLE_ID,
+        points: &[(10.0, 4.0), (30.0, 8.0)],
+    },
+    ScriptedStroke {
+        tool_name: "Circle Brush",
+        particle_id: SAND_PARTICLE_ID,
+        points: &[(6.0, 35.0), (20.0, 35.0), (34.0, 30.0)],
+    },
+    ScriptedStroke {
+        tool_name: "Spray Brush",
+        particle_id: WATER_PARTICLE_ID,
+        points: &[(40.0, 36.0), (40.0, 20.0)],
+    },
+    ScriptedStroke {
+        tool_name: "Circle Brush",
+        particle_id: LAVA_PARTICLE_ID,
+        points: &[(24.0, 20.0)],
+    },
+    ScriptedStroke {
+        tool_name: "Square Brush",
+        particle_id: METHANE_PARTICLE_ID,
+        points: &[(12.0, 14.0), (16.0, 14.0)],
+    },
+    ScriptedStroke {
+        tool_name: "Circle Brush",
+        particle_id: FIRE_PARTICLE_ID,
+        points: &[(20.0, 10.0)],
+    },
+];
+
+// Plays the input script into a fresh engine, then lets the world run on its own
+fn run_script(seed: u64) -> Engine {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_seeded_engine(GRID_WIDTH, GRID_HEIGHT, seed, &mut renderer);
+    let mut input_state: InputState = InputState::default();
+    engine.brush_radius = 3;
+
+    for stroke in &INPUT_SCRIPT {
+        while engine.get_active_tool_name() != stroke.tool_name {
+            engine.cycle_active_tool(1);
+        }
+        engine.selected_particle_id = stroke.particle_id;
+
+        input_state.mouse_left_down = true;
+        for &(x, y) in stroke.points {
+            input_state.mouse_position = Vector2::<f64>::new(x, y);
+            engine.update(&mut renderer, &mut input_state);
+        }
+        input_state.mouse_left_down = false;
+        engine.update(&mut renderer, &mut input_state);
+    }
+
+    for _ in 0..TICK_COUNT {
+        engine.update(&mut renderer, &mut input_state);
+    }
+    return engine;
+}
+
+// FNV-1a over everything a cell stores
+fn hash_grid(engine: &Engine) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut feed = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+
+    for (particle, temperature) in engine.current_grid.data.iter().zip(engine.current_grid.temperature.iter()) {
+        feed(&particle.id.to_le_bytes());
+        feed(&[particle.variant, particle.flags]);
+        feed(&particle.age.to_le_bytes());
+        feed(&particle.burn_timer.to_le_bytes());
+        feed(&particle.get_velocity().x.to_bits().to_le_bytes());
+        feed(&particle.get_velocity().y.to_bits().to_le_bytes());
+        feed(&temperature.to_bits().to_le_bytes());
+    }
+    return hash;
+}
+
+#[test]
+fn same_seed_gives_identical_worlds() {
+    let first: Engine = run_script(1234);
+    let second: Engine = run_script(1234);
+
+    assert_eq!(hash_grid(&first), hash_grid(&second));
+}
+
+#[test]
+fn different_seeds_give_different_worlds() {
+    let first: Engine = run_script(1234);
+    let second: Engine = run_script(5678);
+
+    assert_ne!(hash_grid(&first), hash_grid(&second));
+}
+
+// A world spanning many chunks, so every checkerboard pass has chunks to spread across threads
+fn run_busy_world(seed: u64) -> Engine {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_seeded_engine(256, 192, seed, &mut renderer);
+    let mut input_state: InputState = InputState::default();
+
+    for y in 64..192 {
+        for x in 0..256 {
+            match (x * 7 + y * 13) % 4 {
+                0 => engine.current_grid.create_particle_at(x, y, SAND_PARTICLE_ID, true, true),
+                1 => engine.current_grid.create_particle_at(x, y, WATER_PARTICLE_ID, true, true),
+                _ => false,
+            };
+        }
+    }
+
+    for _ in 0..TICK_COUNT {
+        engine.update(&mut renderer, &mut input_state);
+    }
+    return engine;
+}
+
+#[test]
+fn thread_count_does_not_change_the_world() {
+    let single_thread_pool: rayon::ThreadPool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+    let multi_thread_pool: rayon::ThreadPool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+
+    let single_thread_hash: u64 = single_thread_pool.install(|| hash_grid(&run_busy_world(1234)));
+    let multi_thread_hash: u64 = multi_thread_pool.install(|| hash_grid(&run_busy_world(1234)));
+
+    assert_eq!(single_thread_hash, multi_thread_hash);
+}
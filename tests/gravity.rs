@@ -0,0 +1,129 @@
+mod common;
+
+use bog_engine::core::engine::Engine;
+use bog_engine::io::null_renderer::NullRenderer;
+use bog_engine::io::world_file::{decode_grid, encode_grid};
+use bog_engine::structs::gravity::{Gravity, GravityDirection};
+use bog_engine::structs::grid::Grid;
+
+use std::path::PathBuf;
+
+use common::{SAND_PARTICLE_ID, WATER_PARTICLE_ID, create_seeded_engine, run_ticks};
+
+const GRID_WIDTH: usize = 64;
+const GRID_HEIGHT: usize = 64;
+
+fn create_gravity_engine(renderer: &mut NullRenderer, gravity: Gravity) -> Engine {
+    let mut engine: Engine = create_seeded_engine(GRID_WIDTH, GRID_HEIGHT, 11, renderer);
+    engine.set_gravity(gravity);
+    return engine;
+}
+
+// Positions of every particle with this ID
+fn find_particles(engine: &Engine, particle_id: u16) -> Vec<(i32, i32)> {
+    let width: usize = engine.current_grid.width as usize;
+    return engine
+        .current_grid
+        .data
+        .iter()
+        .enumerate()
+        .filter(|(_, particle)| particle.id == particle_id)
+        .map(|(index, _)| ((index % width) as i32, (index / width) as i32))
+        .collect();
+}
+
+#[test]
+fn sand_falls_whichever_way_gravity_pulls() {
+    let last: i32 = GRID_WIDTH as i32 - 1;
+
+    for direction in [GravityDirection::Down, GravityDirection::Left, GravityDirection::Up, GravityDirection::Right] {
+        let mut renderer: NullRenderer = NullRenderer::new();
+        let mut engine: Engine = create_gravity_engine(&mut renderer, Gravity::new(direction, 1.0));
+        engine.current_grid.create_particle_at(last / 2, last / 2, SAND_PARTICLE_ID, true, true);
+
+        run_ticks(&mut engine, &mut renderer, 100);
+
+        let grains: Vec<(i32, i32)> = find_particles(&engine, SAND_PARTICLE_ID);
+        assert_eq!(grains.len(), 1);
+
+        // The grain ends up against the wall gravity pulls it towards
+        let (x, y) = grains[0];
+        let distance_to_wall: i32 = match direction {
+            GravityDirection::Down => y,
+            GravityDirection::Left => x,
+            GravityDirection::Up => last - y,
+            GravityDirection::Right => last - x,
+        };
+        assert_eq!(distance_to_wall, 0, "{:?} gravity left the grain at {:?}", direction, grains[0]);
+    }
+}
+
+#[test]
+fn turning_gravity_wakes_resting_particles() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_gravity_engine(&mut renderer, Gravity::default());
+    engine.current_grid.fill_circle_at(32, 32, 6, SAND_PARTICLE_ID);
+
+    run_ticks(&mut engine, &mut renderer, 300);
+    assert!(!engine.current_grid.has_dirty_particles());
+    assert!(find_particles(&engine, SAND_PARTICLE_ID).iter().all(|&(_, y)| y < 32));
+
+    // Half a turn later the pile has to fall up to the ceiling
+    engine.rotate_gravity();
+    engine.rotate_gravity();
+    assert_eq!(engine.current_grid.get_gravity().direction, GravityDirection::Up);
+    run_ticks(&mut engine, &mut renderer, 300);
+    assert!(find_particles(&engine, SAND_PARTICLE_ID).iter().all(|&(_, y)| y >= 32));
+}
+
+#[test]
+fn zero_gravity_makes_grains_drift() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_gravity_engine(&mut renderer, Gravity::default());
+    engine.toggle_zero_gravity();
+    assert!(engine.current_grid.get_gravity().is_zero());
+
+    engine.current_grid.fill_circle_at(32, 32, 4, SAND_PARTICLE_ID);
+    let start: Vec<(i32, i32)> = find_particles(&engine, SAND_PARTICLE_ID);
+    run_ticks(&mut engine, &mut renderer, 200);
+    let end: Vec<(i32, i32)> = find_particles(&engine, SAND_PARTICLE_ID);
+
+    // Nothing falls, but the grains have wandered off and are still on the move
+    assert_eq!(end.len(), start.len());
+    assert_ne!(end, start);
+    assert!(end.iter().all(|&(_, y)| y > 16), "{:?}", end);
+    assert!(engine.current_grid.has_dirty_particles());
+
+    // Switching gravity back on brings it back at the strength it had
+    engine.toggle_zero_gravity();
+    assert_eq!(engine.current_grid.get_gravity(), Gravity::default());
+}
+
+#[test]
+fn world_files_keep_their_gravity() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_gravity_engine(&mut renderer, Gravity::new(GravityDirection::Right, 2.5));
+    engine.current_grid.fill_circle_at(20, 20, 4, WATER_PARTICLE_ID);
+    run_ticks(&mut engine, &mut renderer, 5);
+
+    let grid: &Grid = &engine.current_grid;
+    let loaded: Grid = decode_grid(&encode_grid(grid), grid.get_registry()).unwrap();
+    assert_eq!(loaded.get_gravity(), Gravity::new(GravityDirection::Right, 2.5));
+}
+
+#[test]
+fn loaded_worlds_bring_back_their_own_gravity_strength() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let saved_engine: Engine = create_gravity_engine(&mut renderer, Gravity::new(GravityDirection::Right, 2.5));
+    let path: PathBuf = std::env::temp_dir().join(format!("bog_gravity_{}.bog", std::process::id()));
+    saved_engine.save_world(&path).unwrap();
+
+    let mut engine: Engine = create_gravity_engine(&mut renderer, Gravity::default());
+    engine.load_world(&path, &mut renderer).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // Switching zero gravity off again goes back to the saved strength, not this session's
+    engine.toggle_zero_gravity();
+    engine.toggle_zero_gravity();
+    assert_eq!(engine.current_grid.get_gravity(), Gravity::new(GravityDirection::Right, 2.5));
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use bog_engine::core::engine::Engine;
+use bog_engine::io::null_renderer::NullRenderer;
+use bog_engine::loader::load_particle_data;
+use bog_engine::settings::Settings;
+use bog_engine::structs::particle_registry::ParticleRegistry;
+
+use common::{SAND_PARTICLE_ID, STONE_PARTICLE_ID, WATER_PARTICLE_ID, count_particles, create_engine, run_ticks};
+
+const GRID_WIDTH: usize = 64;
+const GRID_HEIGHT: usize = 64;
+#[test]
+fn default_particle_data_loads() {
+    let settings: Settings = Settings::default();
+    let path: String = settings.particle_data_paths[0].display().to_string();
+    let registry: ParticleRegistry = load_particle_data(&path).unwrap();
+
+    assert_eq!(registry.get(SAND_PARTICLE_ID).unwrap().name, "Sand");
+    assert_eq!(registry.get(0).unwrap().name, "Empty");
+}
+
+#[test]
+fn sand_falls_to_the_floor_and_settles() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_engine(GRID_WIDTH, GRID_HEIGHT, &mut renderer);
+
+    engine.current_grid.fill_circle_at(32, 48, 8, SAND_PARTICLE_ID);
+    let sand_count: usize = count_particles(&engine, SAND_PARTICLE_ID);
+    assert!(sand_count > 0);
+
+    run_ticks(&mut engine, &mut renderer, 2000);
+
+    // No grain is created or lost, and nothing is left moving
+    assert_eq!(count_particles(&engine, SAND_PARTICLE_ID), sand_count);
+    assert!(!engine.current_grid.has_dirty_particles());
+
+    // Every grain rests on something
+    let width: i32 = engine.current_grid.width;
+    for (index, _) in engine.current_grid.data.iter().enumerate().filter(|(_, particle)| particle.id == SAND_PARTICLE_ID) {
+        let (x, y) = (index as i32 % width, index as i32 / width);
+        let below = engine.current_grid.get_particle_at(x, y - 1);
+        assert!(below.is_none_or(|below| below.id != 0), "grain at ({}, {}) is floating", x, y);
+    }
+    assert!(renderer.particles_queued > 0);
+}
+
+#[test]
+fn water_levels_out_in_a_basin() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_engine(GRID_WIDTH, GRID_HEIGHT, &mut renderer);
+
+    // Stone walls keep the water away from the grid edges
+    for y in 0..GRID_HEIGHT as i32 {
+        engine.current_grid.create_particle_at(10, y, STONE_PARTICLE_ID, true, true);
+        engine.current_grid.create_particle_at(53, y, STONE_PARTICLE_ID, true, true);
+    }
+    engine.current_grid.fill_circle_at(20, 40, 6, WATER_PARTICLE_ID);
+    let water_count: usize = count_particles(&engine, WATER_PARTICLE_ID);
+
+    run_ticks(&mut engine, &mut renderer, 3000);
+
+    assert_eq!(count_particles(&engine, WATER_PARTICLE_ID), water_count);
+
+    // A level surface means no column holds more than one cell of water above another
+    let mut column_heights: Vec<usize> = Vec::new();
+    for x in 11..53 {
+        let height: usize = (0..GRID_HEIGHT as i32)
+            .filter(|&y| engine.current_grid.get_particle_at(x, y).is_some_and(|particle| particle.id == WATER_PARTICLE_ID))
+            .count();
+        column_heights.push(height);
+    }
+    let lowest: usize = *column_heights.iter().min().unwrap();
+    let highest: usize = *column_heights.iter().max().unwrap();
+    assert!(highest - lowest <= 1, "water surface is uneven: {:?}", column_heights);
+}
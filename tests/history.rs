@@ -0,0 +1,108 @@
+mod common;
+
+use bog_engine::core::engine::Engine;
+use bog_engine::core::history::History;
+use bog_engine::io::null_renderer::NullRenderer;
+use bog_engine::structs::input_state::InputState;
+use bog_engine::structs::utils::Vector2;
+
+use common::{STONE_PARTICLE_ID, create_engine, drag};
+
+const GRID_WIDTH: usize = 64;
+const GRID_HEIGHT: usize = 48;
+
+fn create_history_engine(renderer: &mut NullRenderer, undo_memory_budget: usize) -> Engine {
+    let mut engine: Engine = create_engine(GRID_WIDTH, GRID_HEIGHT, renderer);
+    engine.history = History::new(undo_memory_budget);
+    engine.selected_particle_id = STONE_PARTICLE_ID;
+    engine.brush_radius = 3;
+    return engine;
+}
+
+fn particle_ids(engine: &Engine) -> Vec<u16> {
+    return engine.current_grid.data.iter().map(|particle| particle.id).collect();
+}
+
+#[test]
+fn undo_and_redo_restore_strokes() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_history_engine(&mut renderer, 16 * 1024 * 1024);
+    let empty_ids: Vec<u16> = particle_ids(&engine);
+
+    drag(&mut engine, &mut renderer, &[(10.0, 10.0), (12.0, 10.0), (14.0, 10.0)]);
+    let first_ids: Vec<u16> = particle_ids(&engine);
+    drag(&mut engine, &mut renderer, &[(40.0, 30.0)]);
+    let second_ids: Vec<u16> = particle_ids(&engine);
+    assert_ne!(first_ids, empty_ids);
+    assert_ne!(second_ids, first_ids);
+
+    // Each stroke is one undo step
+    assert!(engine.undo());
+    assert_eq!(particle_ids(&engine), first_ids);
+    assert!(engine.undo());
+    assert_eq!(particle_ids(&engine), empty_ids);
+    assert!(!engine.undo());
+
+    // Restored cells are dirty so they get redrawn
+    assert!(engine.current_grid.is_particle_dirty(10, 10));
+
+    assert!(engine.redo());
+    assert_eq!(particle_ids(&engine), first_ids);
+
+    // Painting again drops what was left to redo
+    drag(&mut engine, &mut renderer, &[(30.0, 5.0)]);
+    assert!(!engine.redo());
+}
+
+#[test]
+fn history_forgets_oldest_strokes_over_budget() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_history_engine(&mut renderer, 0);
+
+    // Measure one stroke, then allow room for just two of them
+    drag(&mut engine, &mut renderer, &[(10.0, 10.0)]);
+    assert!(!engine.history.can_undo());
+
+    let mut engine: Engine = create_history_engine(&mut renderer, 16 * 1024 * 1024);
+    drag(&mut engine, &mut renderer, &[(10.0, 10.0)]);
+    let stroke_size: usize = engine.history.get_memory_used();
+
+    let mut engine: Engine = create_history_engine(&mut renderer, stroke_size * 2);
+    drag(&mut engine, &mut renderer, &[(10.0, 10.0)]);
+    drag(&mut engine, &mut renderer, &[(30.0, 10.0)]);
+    drag(&mut engine, &mut renderer, &[(50.0, 10.0)]);
+    assert!(engine.history.get_memory_used() <= stroke_size * 2);
+
+    assert!(engine.undo());
+    assert!(engine.undo());
+    assert!(!engine.undo());
+
+    // The first stroke could no longer be undone
+    assert_eq!(engine.current_grid.get_particle_at(10, 10).unwrap().id, STONE_PARTICLE_ID);
+    assert_eq!(engine.current_grid.get_particle_at(30, 10).unwrap().id, 0);
+}
+
+#[test]
+fn undo_waits_for_the_stroke_being_painted() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_history_engine(&mut renderer, 16 * 1024 * 1024);
+    let empty_ids: Vec<u16> = particle_ids(&engine);
+    let mut input_state: InputState = InputState {
+        mouse_position: Vector2::<f64>::new(10.0, 10.0),
+        mouse_left_down: true,
+        ..InputState::default()
+    };
+    engine.update(&mut renderer, &mut input_state);
+
+    // Undoing in the middle of a drag does nothing, the rest of the drag still belongs to the same stroke
+    assert!(!engine.undo());
+    assert!(!engine.redo());
+    input_state.mouse_position = Vector2::<f64>::new(30.0, 10.0);
+    engine.update(&mut renderer, &mut input_state);
+    input_state.mouse_left_down = false;
+    engine.update(&mut renderer, &mut input_state);
+    assert_eq!(engine.current_grid.get_particle_at(30, 10).unwrap().id, STONE_PARTICLE_ID);
+
+    assert!(engine.undo());
+    assert_eq!(particle_ids(&engine), empty_ids);
+}
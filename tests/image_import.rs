@@ -0,0 +1,103 @@
+mod common;
+
+use bog_engine::io::image_import::{FitMode, ImageImportError, Palette, import_image};
+use bog_engine::loader::load_particle_data;
+use bog_engine::settings::Settings;
+use bog_engine::structs::particle_registry::ParticleRegistry;
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use common::{SAND_PARTICLE_ID, STONE_PARTICLE_ID, WATER_PARTICLE_ID};
+
+const SAND: [u8; 4] = [0xE0, 0xC5, 0x62, 255];
+const WATER: [u8; 4] = [0x3A, 0x7B, 0xD5, 255];
+const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+fn load_registry() -> ParticleRegistry {
+    let settings: Settings = Settings::default();
+    return load_particle_data(&settings.particle_data_paths[0].display().to_string()).unwrap();
+}
+
+// Writes a small RGBA image, rows are given top first
+fn write_image(name: &str, rows: &[[[u8; 4]; 2]]) -> PathBuf {
+    let path: PathBuf = std::env::temp_dir().join(format!("bog_{}_{}.png", name, std::process::id()));
+    let file: File = File::create(&path).unwrap();
+    let mut encoder: png::Encoder<BufWriter<File>> = png::Encoder::new(BufWriter::new(file), 2, rows.len() as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let pixels: Vec<u8> = rows.iter().flatten().flatten().copied().collect();
+    encoder.write_header().unwrap().write_image_data(&pixels).unwrap();
+    return path;
+}
+
+fn import(path: &Path, palette: &Palette, width: usize, height: usize, fit_mode: FitMode) -> Vec<u16> {
+    let particle_ids: Vec<u16> = import_image(path, palette, width, height, fit_mode).unwrap();
+    return particle_ids;
+}
+
+#[test]
+fn pixels_match_nearest_particle_color() {
+    let registry: ParticleRegistry = load_registry();
+    let palette: Palette = Palette::from_registry(&registry);
+    let path: PathBuf = write_image("palette", &[[CLEAR, WATER], [SAND, SAND]]);
+
+    // Crop keeps pixels one to one, the image's bottom row lands on the grid's bottom row
+    let particle_ids: Vec<u16> = import(&path, &palette, 3, 3, FitMode::Crop);
+    std::fs::remove_file(&path).unwrap();
+
+    #[rustfmt::skip]
+    let expected: Vec<u16> = vec![
+        SAND_PARTICLE_ID, SAND_PARTICLE_ID, 0,
+        0, WATER_PARTICLE_ID, 0,
+        0, 0, 0,
+    ];
+    assert_eq!(particle_ids, expected);
+}
+
+#[test]
+fn scale_stretches_image_over_grid() {
+    let registry: ParticleRegistry = load_registry();
+    let palette: Palette = Palette::from_registry(&registry);
+    let path: PathBuf = write_image("scale", &[[WATER, WATER], [SAND, CLEAR]]);
+
+    let particle_ids: Vec<u16> = import(&path, &palette, 4, 4, FitMode::Scale);
+    std::fs::remove_file(&path).unwrap();
+
+    // Each pixel covers a 2x2 block of cells
+    #[rustfmt::skip]
+    let expected: Vec<u16> = vec![
+        SAND_PARTICLE_ID, SAND_PARTICLE_ID, 0, 0,
+        SAND_PARTICLE_ID, SAND_PARTICLE_ID, 0, 0,
+        WATER_PARTICLE_ID, WATER_PARTICLE_ID, WATER_PARTICLE_ID, WATER_PARTICLE_ID,
+        WATER_PARTICLE_ID, WATER_PARTICLE_ID, WATER_PARTICLE_ID, WATER_PARTICLE_ID,
+    ];
+    assert_eq!(particle_ids, expected);
+}
+
+#[test]
+fn legend_overrides_particle_colors() {
+    let registry: ParticleRegistry = load_registry();
+    let legend: &str = "# Level legend\n10: #E0C562\n\n100: #0000FF\n";
+    let palette: Palette = Palette::from_legend(legend, &registry).unwrap();
+    let path: PathBuf = write_image("legend", &[[SAND, WATER]]);
+
+    // Sand colored pixels are stone in this legend, and water is closer to blue than to sand
+    let particle_ids: Vec<u16> = import(&path, &palette, 2, 1, FitMode::Crop);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(particle_ids, vec![STONE_PARTICLE_ID, WATER_PARTICLE_ID]);
+}
+
+#[test]
+fn legend_reports_bad_lines() {
+    let registry: ParticleRegistry = load_registry();
+
+    let result = Palette::from_legend("10: #E0C562\n10 #FFFFFF\n", &registry);
+    assert!(matches!(result, Err(ImageImportError::MalformedLegendLine { line: 2, .. })));
+
+    let result = Palette::from_legend("9999: #E0C562\n", &registry);
+    assert!(matches!(result, Err(ImageImportError::UnknownParticleId { line: 1, id: 9999 })));
+}
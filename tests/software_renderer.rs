@@ -0,0 +1,97 @@
+mod common;
+
+use bog_engine::core::engine::Engine;
+use bog_engine::io::null_renderer::NullRenderer;
+use bog_engine::io::renderer_interface::RendererInterface;
+use bog_engine::io::snapshot::SnapshotError;
+use bog_engine::io::software_renderer::SoftwareRenderer;
+use bog_engine::structs::input_state::InputState;
+use bog_engine::structs::color::Color;
+use bog_engine::structs::utils::Pixel;
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use common::{STONE_PARTICLE_ID, create_engine};
+
+const GRID_WIDTH: usize = 32;
+const GRID_HEIGHT: usize = 24;
+
+fn cell_rgba(engine: &Engine, x: usize, y: usize) -> [u8; 4] {
+    let color: Color = engine.current_grid.get_color(y * GRID_WIDTH + x);
+    return [color.r, color.g, color.b, color.a];
+}
+
+#[test]
+fn base_layer_shows_particle_colors() {
+    let mut renderer: SoftwareRenderer = SoftwareRenderer::new(GRID_WIDTH, GRID_HEIGHT);
+    let mut engine: Engine = create_engine(GRID_WIDTH, GRID_HEIGHT, &mut renderer);
+
+    // Stone does not move, so it stays where it was placed
+    engine.current_grid.create_particle_at(3, 0, STONE_PARTICLE_ID, true, true);
+    engine.update(&mut renderer, &mut InputState::default());
+    renderer.render_frame();
+
+    let stone: [u8; 4] = cell_rgba(&engine, 3, 0);
+    let empty: [u8; 4] = cell_rgba(&engine, 20, 20);
+    assert_eq!(renderer.get_pixel(3, 0), Some(stone));
+    assert_eq!(renderer.get_pixel(20, 20), Some(empty));
+
+    // Bottom row of the grid is the last row of the image
+    let offset: usize = ((GRID_HEIGHT - 1) * GRID_WIDTH + 3) * 4;
+    assert_eq!(&renderer.get_frame()[offset..offset + 4], &stone);
+}
+
+#[test]
+fn overlay_layer_blends_over_base() {
+    let mut renderer: SoftwareRenderer = SoftwareRenderer::new(GRID_WIDTH, GRID_HEIGHT);
+    let engine: Engine = create_engine(GRID_WIDTH, GRID_HEIGHT, &mut renderer);
+
+    let opaque_index: usize = 5 * GRID_WIDTH + 5;
+    let translucent_index: usize = 5 * GRID_WIDTH + 6;
+    renderer.queue_debug_overlay_pixels(vec![
+        Pixel { r: 255, g: 0, b: 0, a: 255, index: opaque_index },
+        Pixel { r: 255, g: 0, b: 0, a: 128, index: translucent_index },
+    ]);
+    renderer.render_frame();
+
+    // Fully opaque overlay replaces the base color
+    assert_eq!(renderer.get_pixel(5, 5), Some([255, 0, 0, 255]));
+
+    // Half transparent overlay lands between the base color and red
+    let base: [u8; 4] = cell_rgba(&engine, 6, 5);
+    let blended: [u8; 4] = renderer.get_pixel(6, 5).unwrap();
+    assert!(blended[0] > base[0] && blended[0] < 255);
+    assert!(blended[1] <= base[1]);
+}
+
+#[test]
+fn snapshot_round_trips_through_png() {
+    let mut renderer: SoftwareRenderer = SoftwareRenderer::new(GRID_WIDTH, GRID_HEIGHT);
+    let mut engine: Engine = create_engine(GRID_WIDTH, GRID_HEIGHT, &mut renderer);
+
+    engine.current_grid.create_particle_at(7, 0, STONE_PARTICLE_ID, true, true);
+    engine.update(&mut renderer, &mut InputState::default());
+    renderer.render_frame();
+
+    let path: PathBuf = std::env::temp_dir().join(format!("bog_snapshot_{}.png", std::process::id()));
+    renderer.save_snapshot(&path).unwrap();
+
+    let decoder: png::Decoder<File> = png::Decoder::new(File::open(&path).unwrap());
+    let mut reader: png::Reader<File> = decoder.read_info().unwrap();
+    let mut pixels: Vec<u8> = vec![0; reader.output_buffer_size()];
+    let info: png::OutputInfo = reader.next_frame(&mut pixels).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!((info.width as usize, info.height as usize), (GRID_WIDTH, GRID_HEIGHT));
+    assert_eq!(&pixels[..info.buffer_size()], renderer.get_frame());
+}
+
+#[test]
+fn null_renderer_has_nothing_to_snapshot() {
+    let renderer: NullRenderer = NullRenderer::new();
+    let path: PathBuf = std::env::temp_dir().join("bog_snapshot_null.png");
+
+    assert!(matches!(renderer.save_snapshot(&path), Err(SnapshotError::NoFrame)));
+    assert!(!path.exists());
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use bog_engine::core::engine::Engine;
+use bog_engine::core::timestep::{FixedTimestep, MAX_TIME_SCALE};
+use bog_engine::io::null_renderer::NullRenderer;
+use bog_engine::structs::input_state::InputState;
+
+use std::time::Duration;
+
+use common::{SAND_PARTICLE_ID, create_engine};
+
+fn ms(milliseconds: u64) -> Duration {
+    return Duration::from_millis(milliseconds);
+}
+
+#[test]
+fn leftover_time_carries_into_the_next_frame() {
+    let mut timestep: FixedTimestep = FixedTimestep::new(ms(15), 4);
+
+    assert_eq!(timestep.advance(ms(10)), 0);
+    assert_eq!(timestep.advance(ms(10)), 1);
+    assert_eq!(timestep.get_accumulated_time(), ms(5));
+    assert_eq!(timestep.advance(ms(25)), 2);
+    assert_eq!(timestep.get_accumulated_time(), Duration::ZERO);
+}
+
+#[test]
+fn long_frames_are_capped_and_the_backlog_dropped() {
+    let mut timestep: FixedTimestep = FixedTimestep::new(ms(15), 4);
+
+    assert_eq!(timestep.advance(ms(1000)), 4);
+    assert_eq!(timestep.get_accumulated_time(), Duration::ZERO);
+    assert_eq!(timestep.advance(ms(15)), 1);
+}
+
+#[test]
+fn time_scale_slows_down_and_speeds_up() {
+    let mut timestep: FixedTimestep = FixedTimestep::new(ms(10), 8);
+
+    // Half speed needs two frames' worth of time per tick
+    timestep.set_time_scale(0.5);
+    let slow_ticks: Vec<u32> = (0..4).map(|_| timestep.advance(ms(10))).collect();
+    assert_eq!(slow_ticks, vec![0, 1, 0, 1]);
+
+    timestep.set_time_scale(4.0);
+    assert_eq!(timestep.advance(ms(10)), 4);
+
+    timestep.set_time_scale(1000.0);
+    assert_eq!(timestep.get_time_scale(), MAX_TIME_SCALE);
+}
+
+#[test]
+fn fast_forward_runs_every_tick_it_is_due() {
+    let mut timestep: FixedTimestep = FixedTimestep::new(ms(15), 4);
+    timestep.set_time_scale(MAX_TIME_SCALE);
+
+    // Each frame is due eight ticks, more than the catch-up cap, and all of them must run
+    for _ in 0..10 {
+        assert_eq!(timestep.advance(ms(15)), 8);
+    }
+
+    // A slow frame still drops the backlog, only with a cap scaled to the speed
+    assert_eq!(timestep.advance(ms(1000)), 32);
+    assert_eq!(timestep.get_accumulated_time(), Duration::ZERO);
+}
+
+#[test]
+fn frames_run_the_requested_number_of_ticks() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_engine(16, 32, &mut renderer);
+    let mut input_state: InputState = InputState::default();
+
+    engine.current_grid.create_particle_at(8, 20, SAND_PARTICLE_ID, true, true);
+
+    // A frame without ticks still draws, but nothing moves
+    let queued_before: u64 = renderer.particles_queued;
+    engine.run_frame(&mut renderer, &mut input_state, 0);
+    assert_eq!(engine.current_grid.get_particle_at(8, 20).unwrap().id, SAND_PARTICLE_ID);
+    assert!(renderer.particles_queued > queued_before);
+
+    // Each tick lets the grain fall one cell
+    engine.run_frame(&mut renderer, &mut input_state, 3);
+    assert_eq!(engine.current_grid.get_particle_at(8, 17).unwrap().id, SAND_PARTICLE_ID);
+}
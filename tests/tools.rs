@@ -0,0 +1,159 @@
+mod common;
+
+use bog_engine::core::engine::Engine;
+use bog_engine::io::null_renderer::NullRenderer;
+use bog_engine::structs::input_state::InputState;
+use bog_engine::structs::utils::Vector2;
+
+use common::{STONE_PARTICLE_ID, WOOD_PARTICLE_ID, create_engine, drag};
+
+const GRID_WIDTH: usize = 40;
+const GRID_HEIGHT: usize = 30;
+
+fn create_tool_engine(renderer: &mut NullRenderer, tool_name: &str) -> Engine {
+    let mut engine: Engine = create_engine(GRID_WIDTH, GRID_HEIGHT, renderer);
+    engine.selected_particle_id = STONE_PARTICLE_ID;
+    engine.brush_radius = 2;
+
+    while engine.get_active_tool_name() != tool_name {
+        engine.cycle_active_tool(1);
+    }
+    return engine;
+}
+
+fn painted_cells(engine: &Engine, particle_id: u16) -> Vec<(i32, i32)> {
+    let width: i32 = engine.current_grid.width;
+    let mut cells: Vec<(i32, i32)> = (0..engine.current_grid.data.len() as i32)
+        .filter(|&index| engine.current_grid.data[index as usize].id == particle_id)
+        .map(|index| (index % width, index / width))
+        .collect();
+    cells.sort();
+    return cells;
+}
+
+#[test]
+fn square_brush_paints_a_square() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_tool_engine(&mut renderer, "Square Brush");
+
+    drag(&mut engine, &mut renderer, &[(10.5, 10.5)]);
+
+    let cells: Vec<(i32, i32)> = painted_cells(&engine, STONE_PARTICLE_ID);
+    assert_eq!(cells.len(), 16);
+    assert_eq!((cells[0], cells[15]), ((8, 8), (11, 11)));
+}
+
+#[test]
+fn line_tool_draws_between_drag_ends() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_tool_engine(&mut renderer, "Line");
+
+    // Nothing is painted until the drag is released
+    drag(&mut engine, &mut renderer, &[(2.0, 5.0), (20.0, 15.0), (12.0, 9.0)]);
+
+    let cells: Vec<(i32, i32)> = painted_cells(&engine, STONE_PARTICLE_ID);
+    assert_eq!(cells.len(), 11);
+    assert_eq!((cells[0], cells[10]), ((2, 5), (12, 9)));
+    for pair in cells.windows(2) {
+        assert_eq!(pair[1].0 - pair[0].0, 1);
+        assert!((0..=1).contains(&(pair[1].1 - pair[0].1)));
+    }
+}
+
+#[test]
+fn rectangle_tool_fills_between_corners() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_tool_engine(&mut renderer, "Rectangle");
+
+    drag(&mut engine, &mut renderer, &[(15.0, 12.0), (5.0, 3.0)]);
+
+    let cells: Vec<(i32, i32)> = painted_cells(&engine, STONE_PARTICLE_ID);
+    assert_eq!(cells.len(), 11 * 10);
+    assert_eq!((cells[0], cells[cells.len() - 1]), ((5, 3), (15, 12)));
+}
+
+#[test]
+fn flood_fill_stays_inside_walls() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_tool_engine(&mut renderer, "Flood Fill");
+
+    // A closed wooden box with empty space inside
+    for index in engine.current_grid.get_rect_indices(5, 5, 15, 15) {
+        let (x, y) = (index as i32 % GRID_WIDTH as i32, index as i32 / GRID_WIDTH as i32);
+        let is_wall: bool = x == 5 || x == 15 || y == 5 || y == 15;
+        engine.current_grid.create_particle_at(x, y, if is_wall { WOOD_PARTICLE_ID } else { 0 }, true, true);
+    }
+
+    drag(&mut engine, &mut renderer, &[(10.0, 10.0)]);
+
+    assert_eq!(painted_cells(&engine, STONE_PARTICLE_ID).len(), 9 * 9);
+    assert_eq!(painted_cells(&engine, WOOD_PARTICLE_ID).len(), 40);
+}
+
+#[test]
+fn spray_brush_scatters_particles() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_tool_engine(&mut renderer, "Spray Brush");
+    engine.brush_radius = 8;
+
+    drag(&mut engine, &mut renderer, &[(20.0, 15.0)]);
+
+    let circle_size: usize = engine.current_grid.get_circle_indices(20, 15, 8).len();
+    let painted: usize = painted_cells(&engine, STONE_PARTICLE_ID).len();
+    assert!(painted > 0 && painted < circle_size / 2, "{} of {}", painted, circle_size);
+}
+
+#[test]
+fn replace_mask_only_paints_over_one_material() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_tool_engine(&mut renderer, "Rectangle");
+
+    engine.current_grid.create_particle_at(10, 10, WOOD_PARTICLE_ID, true, true);
+    engine.current_grid.create_particle_at(12, 10, WOOD_PARTICLE_ID, true, true);
+    engine.toggle_replace_mask_at(10, 10);
+    assert_eq!(engine.replace_mask, Some(WOOD_PARTICLE_ID));
+
+    drag(&mut engine, &mut renderer, &[(0.0, 0.0), (39.0, 29.0)]);
+    assert_eq!(painted_cells(&engine, STONE_PARTICLE_ID), vec![(10, 10), (12, 10)]);
+
+    // Toggling again paints over everything
+    engine.toggle_replace_mask_at(0, 0);
+    assert_eq!(engine.replace_mask, None);
+}
+
+#[test]
+fn fast_strokes_stay_continuous() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_tool_engine(&mut renderer, "Circle Brush");
+
+    // Two samples far apart, as if the mouse crossed the grid within a single frame
+    drag(&mut engine, &mut renderer, &[(2.0, 15.0), (37.0, 15.0)]);
+
+    for x in 2..=37 {
+        assert_eq!(engine.current_grid.get_particle_at(x, 15).unwrap().id, STONE_PARTICLE_ID, "gap at {}", x);
+    }
+}
+
+#[test]
+fn previous_position_only_lasts_for_a_stroke() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_tool_engine(&mut renderer, "Circle Brush");
+    let mut input_state: InputState = InputState {
+        mouse_position: Vector2::<f64>::new(5.0, 5.0),
+        mouse_left_down: true,
+        ..InputState::default()
+    };
+
+    engine.update(&mut renderer, &mut input_state);
+    assert_eq!(input_state.previous_mouse_position.map(|position| (position.x, position.y)), Some((5.0, 5.0)));
+
+    // Moving with the button up must not draw a line from the last stroke
+    input_state.mouse_left_down = false;
+    engine.update(&mut renderer, &mut input_state);
+    assert!(input_state.previous_mouse_position.is_none());
+
+    input_state.mouse_position = Vector2::<f64>::new(30.0, 5.0);
+    input_state.mouse_left_down = true;
+    engine.update(&mut renderer, &mut input_state);
+    assert_eq!(engine.current_grid.get_particle_at(18, 5).unwrap().id, 0);
+}
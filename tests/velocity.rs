@@ -0,0 +1,145 @@
+mod common;
+
+use bog_engine::core::engine::Engine;
+use bog_engine::io::null_renderer::NullRenderer;
+use bog_engine::structs::input_state::InputState;
+use bog_engine::structs::particle::TERMINAL_VELOCITY;
+use bog_engine::structs::utils::Vector2;
+
+use common::{SAND_PARTICLE_ID, STONE_PARTICLE_ID, WATER_PARTICLE_ID, create_seeded_engine};
+
+const GRID_WIDTH: usize = 64;
+const GRID_HEIGHT: usize = 128;
+const GRAVEL_PARTICLE_ID: u16 = 303;
+
+// Where the single particle with this ID is
+fn find_particle(engine: &Engine, particle_id: u16) -> (i32, i32) {
+    let index: usize = engine.current_grid.data.iter().position(|particle| particle.id == particle_id).unwrap();
+    let width: usize = engine.current_grid.width as usize;
+    return ((index % width) as i32, (index / width) as i32);
+}
+
+#[test]
+fn falling_grains_speed_up_until_terminal_velocity() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_seeded_engine(GRID_WIDTH, GRID_HEIGHT, 3, &mut renderer);
+    let mut input_state: InputState = InputState::default();
+    engine.current_grid.create_particle_at(32, GRID_HEIGHT as i32 - 1, SAND_PARTICLE_ID, true, true);
+
+    // Record how far the grain falls every tick until it hits the floor
+    let mut drops: Vec<i32> = Vec::new();
+    let mut last_y: i32 = GRID_HEIGHT as i32 - 1;
+    while last_y > 0 {
+        engine.update(&mut renderer, &mut input_state);
+        let (_, y) = find_particle(&engine, SAND_PARTICLE_ID);
+        drops.push(last_y - y);
+        last_y = y;
+    }
+
+    // It falls faster over time, but never more than terminal velocity allows
+    assert_eq!(drops[0], 1);
+    assert!(drops.iter().any(|&drop| drop > 1), "{:?}", drops);
+    let free_fall: &[i32] = &drops[..drops.len() - 1];
+    assert!(free_fall.windows(2).all(|pair| pair[1] >= pair[0]), "{:?}", drops);
+    assert!(drops.iter().all(|&drop| drop <= 8), "{:?}", drops);
+
+    // Far fewer ticks than one cell at a time would have taken
+    assert!(drops.len() < GRID_HEIGHT / 2, "{} ticks", drops.len());
+}
+
+#[test]
+fn fast_grains_do_not_tunnel_through_thin_floors() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_seeded_engine(GRID_WIDTH, GRID_HEIGHT, 3, &mut renderer);
+    let mut input_state: InputState = InputState::default();
+
+    // A one cell thick floor, far enough down for grains to reach terminal velocity first
+    for x in 0..GRID_WIDTH as i32 {
+        engine.current_grid.create_particle_at(x, 10, STONE_PARTICLE_ID, true, true);
+    }
+    for x in 20..44 {
+        engine.current_grid.create_particle_at(x, GRID_HEIGHT as i32 - 1, SAND_PARTICLE_ID, true, true);
+    }
+
+    for _ in 0..300 {
+        engine.update(&mut renderer, &mut input_state);
+    }
+
+    let width: usize = GRID_WIDTH;
+    for (index, particle) in engine.current_grid.data.iter().enumerate() {
+        if particle.id == SAND_PARTICLE_ID {
+            assert!(index / width > 10, "grain below the floor at {}", index);
+        }
+    }
+}
+
+#[test]
+fn fast_grains_slow_down_as_soon_as_they_hit_water() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_seeded_engine(GRID_WIDTH, GRID_HEIGHT, 3, &mut renderer);
+    let mut input_state: InputState = InputState::default();
+
+    // A pool of water seven cells deep, the gravel reaches terminal velocity long before it gets there
+    // Gravel rather than sand, since sand turns into wet sand in water
+    for y in 0..7 {
+        for x in 0..GRID_WIDTH as i32 {
+            engine.current_grid.create_particle_at(x, y, WATER_PARTICLE_ID, true, true);
+        }
+    }
+    engine.current_grid.create_particle_at(32, GRID_HEIGHT as i32 - 1, GRAVEL_PARTICLE_ID, true, true);
+
+    let mut last_y: i32 = GRID_HEIGHT as i32 - 1;
+    let mut ticks: usize = 0;
+    while last_y > 0 {
+        engine.update(&mut renderer, &mut input_state);
+        let (_, y) = find_particle(&engine, GRAVEL_PARTICLE_ID);
+
+        // Once the path enters the water the gravel stops there, and then sinks one cell at a time
+        assert!(y >= last_y.min(7) - 1, "fell from {} to {}", last_y, y);
+        last_y = y;
+        ticks += 1;
+        assert!(ticks < 200);
+    }
+}
+
+#[test]
+fn water_splashes_sideways_when_it_lands() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_seeded_engine(GRID_WIDTH, GRID_HEIGHT, 3, &mut renderer);
+    let mut input_state: InputState = InputState::default();
+    engine.current_grid.create_particle_at(32, GRID_HEIGHT as i32 - 1, WATER_PARTICLE_ID, true, true);
+
+    // Run until the drop has hit the floor
+    let mut ticks: usize = 0;
+    while find_particle(&engine, WATER_PARTICLE_ID).1 > 0 {
+        engine.update(&mut renderer, &mut input_state);
+        ticks += 1;
+        assert!(ticks < 200);
+    }
+
+    // The landing's momentum carries it along the floor on the ticks right after
+    let landing_x: i32 = find_particle(&engine, WATER_PARTICLE_ID).0;
+    for _ in 0..2 {
+        engine.update(&mut renderer, &mut input_state);
+    }
+    let (x, y) = find_particle(&engine, WATER_PARTICLE_ID);
+    assert_eq!(y, 0);
+    assert!((x - landing_x).abs() > 2, "slid from {} to {}", landing_x, x);
+}
+
+#[test]
+fn velocities_never_move_a_particle_beyond_terminal_velocity() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_seeded_engine(GRID_WIDTH, GRID_HEIGHT, 3, &mut renderer);
+    let mut input_state: InputState = InputState::default();
+    engine.current_grid.create_particle_at(5, 0, SAND_PARTICLE_ID, true, true);
+
+    // Far faster than anything the physics produces, setting it clamps it to terminal velocity
+    engine.current_grid.data[5].set_velocity(Vector2::<f32>::new(150.0, 0.0));
+    assert_eq!(engine.current_grid.data[5].get_velocity().x, TERMINAL_VELOCITY);
+    engine.update(&mut renderer, &mut input_state);
+
+    let (x, y) = find_particle(&engine, SAND_PARTICLE_ID);
+    assert_eq!(y, 0);
+    assert!(x > 5 && x <= 5 + TERMINAL_VELOCITY as i32, "{}", x);
+}
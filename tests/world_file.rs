@@ -0,0 +1,177 @@
+mod common;
+
+use bog_engine::core::engine::Engine;
+use bog_engine::io::null_renderer::NullRenderer;
+use bog_engine::io::world_file::{WORLD_FILE_VERSION, WorldFileError, decode_grid, encode_grid};
+use bog_engine::structs::grid::Grid;
+use bog_engine::structs::particle::{PARTICLE_FLAG_SCORCHED, TERMINAL_VELOCITY};
+use bog_engine::structs::particle_data::ParticleData;
+use bog_engine::structs::particle_registry::ParticleRegistry;
+use bog_engine::structs::reaction::Reaction;
+
+use std::collections::HashMap;
+
+use common::{FIRE_PARTICLE_ID, LAVA_PARTICLE_ID, SAND_PARTICLE_ID, STONE_PARTICLE_ID, WATER_PARTICLE_ID, WOOD_PARTICLE_ID, create_engine, run_ticks};
+
+const GRID_WIDTH: usize = 48;
+const GRID_HEIGHT: usize = 40;
+
+// Offsets into the encoded file
+const VERSION_OFFSET: usize = 4;
+const FIRST_RUN_ID_OFFSET: usize = 27;
+const FIRST_RUN_VELOCITY_OFFSET: usize = 43;
+
+fn create_busy_engine(renderer: &mut NullRenderer) -> Engine {
+    let mut engine: Engine = create_engine(GRID_WIDTH, GRID_HEIGHT, renderer);
+
+    // Sand, water, lava and burning wood give a mix of ages, temperatures and burn timers
+    engine.current_grid.fill_circle_at(10, 30, 5, SAND_PARTICLE_ID);
+    engine.current_grid.fill_circle_at(24, 30, 5, WATER_PARTICLE_ID);
+    engine.current_grid.fill_circle_at(38, 8, 4, LAVA_PARTICLE_ID);
+    engine.current_grid.fill_circle_at(38, 18, 4, WOOD_PARTICLE_ID);
+    engine.current_grid.fill_circle_at(38, 13, 2, FIRE_PARTICLE_ID);
+    run_ticks(&mut engine, renderer, 50);
+
+    return engine;
+}
+
+#[test]
+fn world_round_trips_every_cell() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let engine: Engine = create_busy_engine(&mut renderer);
+    let grid: &Grid = &engine.current_grid;
+
+    // Something has to be burning, or burn timers and flags aren't checked at all
+    assert!(grid.data.iter().any(|particle| {
+        particle.id == WOOD_PARTICLE_ID && particle.burn_timer > 0 && particle.has_flag(PARTICLE_FLAG_SCORCHED)
+    }));
+
+    let bytes: Vec<u8> = encode_grid(grid);
+    let loaded: Grid = decode_grid(&bytes, grid.get_registry()).unwrap();
+
+    assert_eq!((loaded.width, loaded.height), (grid.width, grid.height));
+    for (index, (original, restored)) in grid.data.iter().zip(loaded.data.iter()).enumerate() {
+        assert_eq!(original.id, restored.id, "cell {}", index);
+        assert_eq!(original.variant, restored.variant, "cell {}", index);
+        assert_eq!(original.age, restored.age, "cell {}", index);
+        assert_eq!(original.burn_timer, restored.burn_timer, "cell {}", index);
+        assert_eq!(original.flags, restored.flags, "cell {}", index);
+        assert_eq!(original.get_velocity(), restored.get_velocity(), "cell {}", index);
+        assert_eq!(grid.get_color(index).to_hex(), loaded.get_color(index).to_hex(), "cell {}", index);
+        assert_eq!(grid.temperature[index].to_bits(), loaded.temperature[index].to_bits(), "cell {}", index);
+    }
+}
+
+#[test]
+fn empty_world_is_compact() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let mut engine: Engine = create_engine(GRID_WIDTH, GRID_HEIGHT, &mut renderer);
+    engine.current_grid.create_particle_at(5, 0, STONE_PARTICLE_ID, true, true);
+
+    // Header plus three runs: empty space, the stone, and empty space again
+    let bytes: Vec<u8> = encode_grid(&engine.current_grid);
+    assert_eq!(bytes.len(), 23 + 3 * 28);
+}
+
+#[test]
+fn world_rejects_other_versions() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let engine: Engine = create_busy_engine(&mut renderer);
+
+    let mut bytes: Vec<u8> = encode_grid(&engine.current_grid);
+    bytes[VERSION_OFFSET..VERSION_OFFSET + 2].copy_from_slice(&(WORLD_FILE_VERSION + 1).to_le_bytes());
+
+    let result = decode_grid(&bytes, engine.current_grid.get_registry());
+    assert!(matches!(result, Err(WorldFileError::UnsupportedVersion(version)) if version == WORLD_FILE_VERSION + 1));
+}
+
+#[test]
+fn world_rejects_unknown_particle_ids() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let engine: Engine = create_busy_engine(&mut renderer);
+
+    let mut bytes: Vec<u8> = encode_grid(&engine.current_grid);
+    bytes[FIRST_RUN_ID_OFFSET..FIRST_RUN_ID_OFFSET + 2].copy_from_slice(&9999u16.to_le_bytes());
+
+    let result = decode_grid(&bytes, engine.current_grid.get_registry());
+    assert!(matches!(result, Err(WorldFileError::UnknownParticleId { id: 9999, index: 0 })));
+}
+
+#[test]
+fn world_rejects_velocities_that_are_not_numbers() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let engine: Engine = create_busy_engine(&mut renderer);
+
+    for velocity in [f32::NAN, f32::INFINITY] {
+        let mut bytes: Vec<u8> = encode_grid(&engine.current_grid);
+        bytes[FIRST_RUN_VELOCITY_OFFSET..FIRST_RUN_VELOCITY_OFFSET + 4].copy_from_slice(&velocity.to_bits().to_le_bytes());
+
+        let result = decode_grid(&bytes, engine.current_grid.get_registry());
+        assert!(matches!(result, Err(WorldFileError::InvalidVelocity { index: 0 })), "{}", velocity);
+    }
+}
+
+#[test]
+fn world_clamps_saved_velocities() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let engine: Engine = create_busy_engine(&mut renderer);
+
+    let mut bytes: Vec<u8> = encode_grid(&engine.current_grid);
+    bytes[FIRST_RUN_VELOCITY_OFFSET..FIRST_RUN_VELOCITY_OFFSET + 4].copy_from_slice(&(-150.0f32).to_bits().to_le_bytes());
+
+    let loaded: Grid = decode_grid(&bytes, engine.current_grid.get_registry()).unwrap();
+    assert_eq!(loaded.data[0].get_velocity().x, -TERMINAL_VELOCITY);
+}
+
+#[test]
+fn world_rejects_different_particle_data() {
+    let mut renderer: NullRenderer = NullRenderer::new();
+    let engine: Engine = create_busy_engine(&mut renderer);
+    let bytes: Vec<u8> = encode_grid(&engine.current_grid);
+
+    let mut registry: ParticleRegistry = engine.current_grid.get_registry().clone();
+    registry.particles.get_mut(&SAND_PARTICLE_ID).unwrap().density += 1.0;
+
+    let result = decode_grid(&bytes, &registry);
+    assert!(matches!(result, Err(WorldFileError::RegistryMismatch { .. })));
+}
+
+#[test]
+fn registry_hash_is_stable() {
+    let stone: ParticleData = ParticleData {
+        id: 10,
+        name: "Stone".to_string(),
+        category: 1,
+        base_color: "#7C7872".to_string(),
+        variant_color: "#5E5A55".to_string(),
+        is_movable: false,
+        density: 55.0,
+        dispersion: 0,
+        lifetime: 0,
+        repose_angle: 45.0,
+        temperature: 20.0,
+        thermal_conductivity: 0.3,
+        heat_capacity: 2.0,
+        melt_point: Some(1200.0),
+        melt_into: Some(102),
+        boil_point: None,
+        boil_into: None,
+        flammability: 0.0,
+        burn_time: 0,
+        burn_into: 0,
+    };
+    let reaction: Reaction = Reaction {
+        reactant_a: 10,
+        reactant_b: 10,
+        product_a: 10,
+        product_b: 0,
+        probability: 0.5,
+    };
+    let registry: ParticleRegistry = ParticleRegistry {
+        particles: HashMap::from([(10, stone)]),
+        reactions: vec![reaction],
+    };
+
+    // Saves keep this hash, if it changes every existing world stops loading
+    assert_eq!(registry.content_hash(), 0x290A_3E11_A511_7E24);
+}